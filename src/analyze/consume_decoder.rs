@@ -1,8 +1,10 @@
 use super::component::BvGraphComponent;
 use webgraph::prelude::*;
 
-/// A decoder wrapper that perform a side effect on each decoded integer.
-pub struct ConsumerDecoderFactory<F: SequentialDecoderFactory, C: Fn(BvGraphComponent, u64)> {
+/// A decoder wrapper that perform a side effect on each decoded integer, which
+/// is passed with its component and the node whose record is being decoded.
+pub struct ConsumerDecoderFactory<F: SequentialDecoderFactory, C: Fn(usize, BvGraphComponent, u64)>
+{
     factory: F,
     consumer: C,
 }
@@ -10,7 +12,7 @@ pub struct ConsumerDecoderFactory<F: SequentialDecoderFactory, C: Fn(BvGraphComp
 impl<F, C> ConsumerDecoderFactory<F, C>
 where
     F: SequentialDecoderFactory,
-    C: Fn(BvGraphComponent, u64),
+    C: Fn(usize, BvGraphComponent, u64),
 {
     pub fn new(factory: F, consumer: C) -> Self {
         Self { factory, consumer }
@@ -20,7 +22,7 @@ where
 impl<F, C> SequentialDecoderFactory for ConsumerDecoderFactory<F, C>
 where
     F: SequentialDecoderFactory,
-    C: Fn(BvGraphComponent, u64) + Clone,
+    C: Fn(usize, BvGraphComponent, u64) + Clone,
 {
     type Decoder<'a>
         = ConsumerDecoder<F::Decoder<'a>, C>
@@ -38,83 +40,88 @@ where
 
 /// A wrapper over a generic [`Decode`] that keeps track of how much
 /// bits each piece would take using different codes for compressions
-pub struct ConsumerDecoder<D: Decode, C: Fn(BvGraphComponent, u64)> {
+pub struct ConsumerDecoder<D: Decode, C: Fn(usize, BvGraphComponent, u64)> {
     codes_reader: D,
     consumer: C,
+    /// The node whose record is being decoded (`usize::MAX` before the first one).
+    node: usize,
 }
 
-impl<D: Decode, C: Fn(BvGraphComponent, u64)> ConsumerDecoder<D, C> {
+impl<D: Decode, C: Fn(usize, BvGraphComponent, u64)> ConsumerDecoder<D, C> {
     /// Wrap a reader
     #[inline(always)]
     pub fn new(decoder: D, consumer: C) -> Self {
         Self {
             codes_reader: decoder,
             consumer,
+            node: usize::MAX,
         }
     }
 }
 
-impl<D: Decode, C: Fn(BvGraphComponent, u64)> Decode for ConsumerDecoder<D, C> {
+impl<D: Decode, C: Fn(usize, BvGraphComponent, u64)> Decode for ConsumerDecoder<D, C> {
     #[inline(always)]
     fn read_outdegree(&mut self) -> u64 {
+        // the outdegree is the first value of the record of a node
+        self.node = self.node.wrapping_add(1);
         let decoded = self.codes_reader.read_outdegree();
-        (self.consumer)(BvGraphComponent::Outdegree, decoded);
+        (self.consumer)(self.node, BvGraphComponent::Outdegree, decoded);
         decoded
     }
 
     #[inline(always)]
     fn read_reference_offset(&mut self) -> u64 {
         let decoded = self.codes_reader.read_reference_offset();
-        (self.consumer)(BvGraphComponent::ReferenceOffset, decoded);
+        (self.consumer)(self.node, BvGraphComponent::ReferenceOffset, decoded);
         decoded
     }
 
     #[inline(always)]
     fn read_block_count(&mut self) -> u64 {
         let decoded = self.codes_reader.read_block_count();
-        (self.consumer)(BvGraphComponent::BlockCount, decoded);
+        (self.consumer)(self.node, BvGraphComponent::BlockCount, decoded);
         decoded
     }
 
     #[inline(always)]
     fn read_block(&mut self) -> u64 {
         let decoded = self.codes_reader.read_block();
-        (self.consumer)(BvGraphComponent::Blocks, decoded);
+        (self.consumer)(self.node, BvGraphComponent::Blocks, decoded);
         decoded
     }
 
     #[inline(always)]
     fn read_interval_count(&mut self) -> u64 {
         let decoded = self.codes_reader.read_interval_count();
-        (self.consumer)(BvGraphComponent::IntervalCount, decoded);
+        (self.consumer)(self.node, BvGraphComponent::IntervalCount, decoded);
         decoded
     }
 
     #[inline(always)]
     fn read_interval_start(&mut self) -> u64 {
         let decoded = self.codes_reader.read_interval_start();
-        (self.consumer)(BvGraphComponent::IntervalStart, decoded);
+        (self.consumer)(self.node, BvGraphComponent::IntervalStart, decoded);
         decoded
     }
 
     #[inline(always)]
     fn read_interval_len(&mut self) -> u64 {
         let decoded = self.codes_reader.read_interval_len();
-        (self.consumer)(BvGraphComponent::IntervalLen, decoded);
+        (self.consumer)(self.node, BvGraphComponent::IntervalLen, decoded);
         decoded
     }
 
     #[inline(always)]
     fn read_first_residual(&mut self) -> u64 {
         let decoded = self.codes_reader.read_first_residual();
-        (self.consumer)(BvGraphComponent::FirstResidual, decoded);
+        (self.consumer)(self.node, BvGraphComponent::FirstResidual, decoded);
         decoded
     }
 
     #[inline(always)]
     fn read_residual(&mut self) -> u64 {
         let decoded = self.codes_reader.read_residual();
-        (self.consumer)(BvGraphComponent::Residual, decoded);
+        (self.consumer)(self.node, BvGraphComponent::Residual, decoded);
         decoded
     }
 }
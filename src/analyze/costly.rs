@@ -0,0 +1,307 @@
+use anyhow::Result;
+use clap::{ArgMatches, Args, Command, FromArgMatches, ValueEnum};
+use dsi_bitstream::{dispatch::factory::CodesReaderFactoryHelper, prelude::*};
+use dsi_progress_logger::prelude::*;
+use lender::*;
+use std::{
+    cell::RefCell,
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    path::PathBuf,
+};
+use webgraph::prelude::*;
+
+use crate::{analyze::component::BvGraphComponent, dispatch_endianness};
+
+pub const COMMAND_NAME: &str = "costly";
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Reads a BvGraph and prints the nodes whose records use the most bits.",
+    long_about = "Reads a BvGraph, measuring the exact number of bits used by the record of each node from the position in the bit stream before and after decoding each of its components, and prints the nodes with the largest number of bits and with the largest number of bits per arc, with their outdegree, the node they reference and the bits used by each component."
+)]
+pub struct CliArgs {
+    /// The basename of the graph.
+    pub src: PathBuf,
+
+    /// The number of nodes to report.
+    #[arg(short = 'k', long, default_value_t = 10)]
+    pub top: usize,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    dispatch_endianness!(&args.src, costly_nodes(args))
+}
+
+pub fn costly_nodes<E: Endianness + 'static>(args: CliArgs) -> Result<()>
+where
+    MmapHelper<u32>: CodesReaderFactoryHelper<E>,
+    for<'a> LoadModeCodesReader<'a, E, Mmap>: BitSeek,
+{
+    // the cost of the record being decoded, filled by the decoder
+    let current = RefCell::new(NodeCost::default());
+    let graph = BvGraphSeq::with_basename(&args.src)
+        .endianness::<E>()
+        .load()?
+        .map_factory(|factory| CostDecoderFactory::new(factory, &current));
+
+    let mut by_bits = TopK::new(args.top);
+    let mut by_bits_per_arc = TopK::new(args.top);
+
+    let mut pl = ProgressLogger::default();
+    pl.display_memory(true)
+        .item_name("node")
+        .expected_updates(Some(graph.num_nodes()));
+
+    pl.start("Scanning...");
+
+    let mut iter = graph.iter();
+    while let Some((node, _)) = iter.next() {
+        // the record of the node has been completely decoded
+        let mut cost = current.take();
+        cost.node = node;
+        let bits = cost.bits();
+        if cost.outdegree > 0 {
+            by_bits_per_arc.push(bits as f64 / cost.outdegree as f64, &cost);
+        }
+        by_bits.push(bits as f64, &cost);
+        pl.light_update();
+    }
+    pl.done();
+
+    println!("Top {} nodes by bits:", args.top);
+    print_table(&by_bits.into_sorted_vec());
+    println!();
+    println!("Top {} nodes by bits per arc:", args.top);
+    print_table(&by_bits_per_arc.into_sorted_vec());
+    Ok(())
+}
+
+/// The bits used by the record of a node.
+#[derive(Clone, Debug, Default)]
+struct NodeCost {
+    node: usize,
+    outdegree: u64,
+    /// The reference offset (zero if the node does not reference another node).
+    reference_offset: u64,
+    /// The bits used by each component, indexed by [`BvGraphComponent`].
+    component_bits: [u64; BvGraphComponent::COMPONENTS],
+}
+
+impl NodeCost {
+    fn bits(&self) -> u64 {
+        self.component_bits.iter().sum()
+    }
+
+    /// The node referenced by this node, if any.
+    fn reference(&self) -> Option<usize> {
+        (self.reference_offset > 0).then(|| self.node - self.reference_offset as usize)
+    }
+}
+
+/// A node with the key it is ranked by.
+struct Ranked {
+    key: f64,
+    cost: NodeCost,
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    /// Orders by key, and by node (smaller nodes first) among equal keys.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key
+            .total_cmp(&other.key)
+            .then_with(|| other.cost.node.cmp(&self.cost.node))
+    }
+}
+
+/// The `k` nodes with the largest keys pushed so far.
+struct TopK {
+    k: usize,
+    heap: BinaryHeap<Reverse<Ranked>>,
+}
+
+impl TopK {
+    fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    fn push(&mut self, key: f64, cost: &NodeCost) {
+        if self.heap.len() == self.k {
+            match self.heap.peek() {
+                Some(Reverse(min)) if min.key < key => {
+                    self.heap.pop();
+                }
+                _ => return,
+            }
+        }
+        self.heap.push(Reverse(Ranked {
+            key,
+            cost: cost.clone(),
+        }));
+    }
+
+    /// Returns the nodes by decreasing key.
+    fn into_sorted_vec(self) -> Vec<Ranked> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(ranked)| ranked)
+            .collect()
+    }
+}
+
+fn print_table(nodes: &[Ranked]) {
+    print!(
+        "{:>12} {:>12} {:>12} {:>12} {:>12}",
+        "Node", "Outdegree", "Reference", "Bits", "Bits/arc"
+    );
+    for component in BvGraphComponent::value_variants() {
+        print!(" {:>16}", component.to_string());
+    }
+    println!();
+    for Ranked { cost, .. } in nodes {
+        print!(
+            "{:>12} {:>12} {:>12} {:>12} {:>12}",
+            cost.node,
+            cost.outdegree,
+            cost.reference()
+                .map_or_else(|| "-".to_string(), |reference| reference.to_string()),
+            cost.bits(),
+            if cost.outdegree > 0 {
+                format!("{:.3}", cost.bits() as f64 / cost.outdegree as f64)
+            } else {
+                "-".to_string()
+            },
+        );
+        for bits in cost.component_bits {
+            print!(" {:>16}", bits);
+        }
+        println!();
+    }
+}
+
+/// A decoder wrapper that measures the bits used by each component of
+/// the record of the node being decoded.
+struct CostDecoderFactory<'b, F: SequentialDecoderFactory> {
+    factory: F,
+    current: &'b RefCell<NodeCost>,
+}
+
+impl<'b, F: SequentialDecoderFactory> CostDecoderFactory<'b, F> {
+    fn new(factory: F, current: &'b RefCell<NodeCost>) -> Self {
+        Self { factory, current }
+    }
+}
+
+impl<'b, F> SequentialDecoderFactory for CostDecoderFactory<'b, F>
+where
+    F: SequentialDecoderFactory,
+    for<'a> F::Decoder<'a>: BitSeek,
+{
+    type Decoder<'a>
+        = CostDecoder<'a, F::Decoder<'a>>
+    where
+        Self: 'a;
+
+    #[inline(always)]
+    fn new_decoder(&self) -> anyhow::Result<Self::Decoder<'_>> {
+        Ok(CostDecoder {
+            codes_reader: self.factory.new_decoder()?,
+            current: self.current,
+        })
+    }
+}
+
+/// A wrapper over a [`Decode`] with a [`BitSeek`] that adds the bits used by
+/// each decoded integer to the cost of the current node.
+struct CostDecoder<'a, D: Decode + BitSeek> {
+    codes_reader: D,
+    current: &'a RefCell<NodeCost>,
+}
+
+impl<D: Decode + BitSeek> CostDecoder<'_, D> {
+    /// Decodes a value with `read`, adding its bits to `component`.
+    #[inline(always)]
+    fn measure(&mut self, component: BvGraphComponent, read: impl FnOnce(&mut D) -> u64) -> u64 {
+        let start = self.codes_reader.bit_pos().unwrap();
+        let value = read(&mut self.codes_reader);
+        let end = self.codes_reader.bit_pos().unwrap();
+        self.current.borrow_mut().component_bits[component as usize] += end - start;
+        value
+    }
+}
+
+impl<D: Decode + BitSeek> Decode for CostDecoder<'_, D> {
+    #[inline(always)]
+    fn read_outdegree(&mut self) -> u64 {
+        let outdegree = self.measure(BvGraphComponent::Outdegree, D::read_outdegree);
+        self.current.borrow_mut().outdegree = outdegree;
+        outdegree
+    }
+
+    #[inline(always)]
+    fn read_reference_offset(&mut self) -> u64 {
+        let reference_offset =
+            self.measure(BvGraphComponent::ReferenceOffset, D::read_reference_offset);
+        self.current.borrow_mut().reference_offset = reference_offset;
+        reference_offset
+    }
+
+    #[inline(always)]
+    fn read_block_count(&mut self) -> u64 {
+        self.measure(BvGraphComponent::BlockCount, D::read_block_count)
+    }
+
+    #[inline(always)]
+    fn read_block(&mut self) -> u64 {
+        self.measure(BvGraphComponent::Blocks, D::read_block)
+    }
+
+    #[inline(always)]
+    fn read_interval_count(&mut self) -> u64 {
+        self.measure(BvGraphComponent::IntervalCount, D::read_interval_count)
+    }
+
+    #[inline(always)]
+    fn read_interval_start(&mut self) -> u64 {
+        self.measure(BvGraphComponent::IntervalStart, D::read_interval_start)
+    }
+
+    #[inline(always)]
+    fn read_interval_len(&mut self) -> u64 {
+        self.measure(BvGraphComponent::IntervalLen, D::read_interval_len)
+    }
+
+    #[inline(always)]
+    fn read_first_residual(&mut self) -> u64 {
+        self.measure(BvGraphComponent::FirstResidual, D::read_first_residual)
+    }
+
+    #[inline(always)]
+    fn read_residual(&mut self) -> u64 {
+        self.measure(BvGraphComponent::Residual, D::read_residual)
+    }
+}
@@ -1,6 +1,7 @@
-use webgraph::prelude::*;
+use super::component::BvGraphComponent;
 use dsi_bitstream::prelude::*;
 use std::sync::Mutex;
+use webgraph::prelude::*;
 
 #[derive(Default, Debug)]
 pub struct CodesStatsWithCount {
@@ -48,7 +49,38 @@ pub struct DecoderStatsAndCount {
 }
 
 impl DecoderStatsAndCount {
-    fn update(&mut self, rhs: &Self) {
+    /// Returns the statistics of the given component.
+    pub fn get(&self, component: BvGraphComponent) -> &CodesStatsWithCount {
+        match component {
+            BvGraphComponent::Outdegree => &self.outdegrees,
+            BvGraphComponent::ReferenceOffset => &self.reference_offsets,
+            BvGraphComponent::BlockCount => &self.block_counts,
+            BvGraphComponent::Blocks => &self.blocks,
+            BvGraphComponent::IntervalCount => &self.interval_counts,
+            BvGraphComponent::IntervalStart => &self.interval_starts,
+            BvGraphComponent::IntervalLen => &self.interval_lens,
+            BvGraphComponent::FirstResidual => &self.first_residuals,
+            BvGraphComponent::Residual => &self.residuals,
+        }
+    }
+
+    /// Returns a mutable reference to the statistics of the given component.
+    pub fn get_mut(&mut self, component: BvGraphComponent) -> &mut CodesStatsWithCount {
+        match component {
+            BvGraphComponent::Outdegree => &mut self.outdegrees,
+            BvGraphComponent::ReferenceOffset => &mut self.reference_offsets,
+            BvGraphComponent::BlockCount => &mut self.block_counts,
+            BvGraphComponent::Blocks => &mut self.blocks,
+            BvGraphComponent::IntervalCount => &mut self.interval_counts,
+            BvGraphComponent::IntervalStart => &mut self.interval_starts,
+            BvGraphComponent::IntervalLen => &mut self.interval_lens,
+            BvGraphComponent::FirstResidual => &mut self.first_residuals,
+            BvGraphComponent::Residual => &mut self.residuals,
+        }
+    }
+
+    /// Combines additively these stats with other ones.
+    pub fn add(&mut self, rhs: &Self) {
         self.outdegrees.add(&rhs.outdegrees);
         self.reference_offsets.add(&rhs.reference_offsets);
         self.block_counts.add(&rhs.block_counts);
@@ -101,51 +133,100 @@ where
     F: SequentialDecoderFactory,
 {
     type Decoder<'a>
-        = StatsDecoder<'a, F>
+        = StatsDecoder<'a, F::Decoder<'a>>
     where
         Self: 'a;
 
     #[inline(always)]
     fn new_decoder(&self) -> anyhow::Result<Self::Decoder<'_>> {
         Ok(StatsDecoder::new(
-            self,
+            Some(&self.glob_stats),
             self.factory.new_decoder()?,
             DecoderStatsAndCount::default(),
         ))
     }
 }
 
+/// A wrapper over a [`RandomAccessDecoderFactory`] to decode sequentially
+/// the chunk of nodes starting at `first_node`, that adds to `stats` only
+/// the stats of the decoder created for `first_node`.
+///
+/// A sequential iterator starting from `first_node` fills its window of
+/// references by decoding the previous nodes with random access, but their
+/// values are already accounted for by the chunk they belong to.
+pub struct ChunkDecoderFactory<'b, F> {
+    factory: &'b F,
+    first_node: usize,
+    stats: &'b Mutex<DecoderStatsAndCount>,
+}
+
+impl<'b, F> ChunkDecoderFactory<'b, F>
+where
+    F: RandomAccessDecoderFactory,
+{
+    pub fn new(factory: &'b F, first_node: usize, stats: &'b Mutex<DecoderStatsAndCount>) -> Self {
+        Self {
+            factory,
+            first_node,
+            stats,
+        }
+    }
+}
+
+impl<'b, F> RandomAccessDecoderFactory for ChunkDecoderFactory<'b, F>
+where
+    F: RandomAccessDecoderFactory,
+{
+    type Decoder<'a>
+        = StatsDecoder<'a, F::Decoder<'a>>
+    where
+        Self: 'a;
+
+    #[inline(always)]
+    fn new_decoder(&self, node: usize) -> anyhow::Result<Self::Decoder<'_>> {
+        Ok(StatsDecoder::new(
+            (node == self.first_node).then_some(self.stats),
+            self.factory.new_decoder(node)?,
+            DecoderStatsAndCount::default(),
+        ))
+    }
+}
+
 /// A wrapper over a generic [`Decode`] that keeps track of how much
 /// bits each piece would take using different codes for compressions
-pub struct StatsDecoder<'a, F: SequentialDecoderFactory> {
-    factory: &'a StatsAndCountDecoderFactory<F>,
-    codes_reader: F::Decoder<'a>,
+pub struct StatsDecoder<'a, D: Decode> {
+    /// The stats the ones of this decoder are added to when it is dropped,
+    /// if any.
+    glob_stats: Option<&'a Mutex<DecoderStatsAndCount>>,
+    codes_reader: D,
     stats: DecoderStatsAndCount,
 }
 
-impl<F: SequentialDecoderFactory> Drop for StatsDecoder<'_, F> {
+impl<D: Decode> Drop for StatsDecoder<'_, D> {
     fn drop(&mut self) {
-        self.factory.glob_stats.lock().unwrap().update(&self.stats);
+        if let Some(glob_stats) = self.glob_stats {
+            glob_stats.lock().unwrap().add(&self.stats);
+        }
     }
 }
 
-impl<'a, F: SequentialDecoderFactory> StatsDecoder<'a, F> {
+impl<'a, D: Decode> StatsDecoder<'a, D> {
     /// Wrap a reader
     #[inline(always)]
     pub fn new(
-        factory: &'a StatsAndCountDecoderFactory<F>,
-        codes_reader: F::Decoder<'a>,
+        glob_stats: Option<&'a Mutex<DecoderStatsAndCount>>,
+        codes_reader: D,
         stats: DecoderStatsAndCount,
     ) -> Self {
         Self {
-            factory,
+            glob_stats,
             codes_reader,
             stats,
         }
     }
 }
 
-impl<F: SequentialDecoderFactory> Decode for StatsDecoder<'_, F> {
+impl<D: Decode> Decode for StatsDecoder<'_, D> {
     #[inline(always)]
     fn read_outdegree(&mut self) -> u64 {
         self.stats
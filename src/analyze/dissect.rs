@@ -1,12 +1,21 @@
-use anyhow::Result;
-use clap::{ArgMatches, Args, Command, FromArgMatches};
+use anyhow::{Context, Result};
+use clap::{ArgMatches, Args, Command, FromArgMatches, ValueEnum};
 use dsi_bitstream::{dispatch::factory::CodesReaderFactoryHelper, prelude::*};
 use dsi_progress_logger::prelude::*;
 use lender::*;
-use std::path::PathBuf;
+use rayon::prelude::*;
+use std::{cell::RefCell, path::PathBuf, sync::Mutex};
 use webgraph::prelude::*;
 
-use crate::analyze::StatsAndCountDecoderFactory;
+use crate::{
+    analyze::{
+        component::BvGraphComponent, ChunkDecoderFactory, ConsumerDecoderFactory,
+        DecoderStatsAndCount, StatsAndCountDecoderFactory,
+    },
+    codes::Code,
+    dispatch_endianness,
+    utils::{append_extension, CHUNKS_PER_THREAD},
+};
 
 pub const COMMAND_NAME: &str = "dissect";
 
@@ -15,6 +24,37 @@ pub const COMMAND_NAME: &str = "dissect";
 pub struct CliArgs {
     /// The basename of the graph.
     pub src: PathBuf,
+
+    /// The number of threads used to scan the graph when the .ef file is
+    /// available (defaults to the number of available cores).
+    #[arg(short, long)]
+    pub threads: Option<usize>,
+
+    /// The format of the report.
+    #[arg(short, long, value_enum, default_value = "table")]
+    pub format: Format,
+
+    /// Instead of the report, print as CSV the bits used by each component in
+    /// each of this number (at most 10000) of ranges of consecutive nodes of the
+    /// same size.
+    #[arg(long, conflicts_with_all = ["range_size", "format"])]
+    pub buckets: Option<usize>,
+
+    /// Instead of the report, print as CSV the bits used by each component in
+    /// each range of this number of consecutive nodes, with at most 10000 ranges.
+    #[arg(long, conflicts_with = "format")]
+    pub range_size: Option<usize>,
+}
+
+/// The formats of the report of `dissect`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Human-readable tables.
+    Table,
+    /// A JSON object, with an entry for each component.
+    Json,
+    /// A CSV file, with a line for each component.
+    Csv,
 }
 
 pub fn cli(command: Command) -> Command {
@@ -24,11 +64,7 @@ pub fn cli(command: Command) -> Command {
 pub fn main(submatches: &ArgMatches) -> Result<()> {
     let args = CliArgs::from_arg_matches(submatches)?;
 
-    match get_endianness(&args.src)?.as_str() {
-        BE::NAME => dissect_graph::<BE>(args),
-        LE::NAME => dissect_graph::<LE>(args),
-        e => panic!("Unknown endianness: {}", e),
-    }
+    dispatch_endianness!(&args.src, dissect_graph(args))
 }
 
 pub fn dissect_graph<E: Endianness + 'static>(args: CliArgs) -> Result<()>
@@ -36,8 +72,90 @@ where
     MmapHelper<u32>: CodesReaderFactoryHelper<E>,
     for<'a> LoadModeCodesReader<'a, E, Mmap>: BitSeek,
 {
-    // TODO!: speed it up by using random access graph if possible
-    let graph = BvGraphSeq::with_basename(args.src)
+    // the codes actually used to compress the graph
+    let (num_nodes, num_arcs, flags) =
+        parse_properties::<E>(append_extension(&args.src, PROPERTIES_EXTENSION))?;
+
+    let range_size = match (args.buckets, args.range_size) {
+        (Some(buckets), _) => {
+            anyhow::ensure!(buckets > 0, "The number of buckets must be positive");
+            Some(num_nodes.div_ceil(buckets).max(1))
+        }
+        (None, Some(range_size)) => {
+            anyhow::ensure!(range_size > 0, "The range size must be positive");
+            Some(range_size)
+        }
+        (None, None) => None,
+    };
+    let has_ef = append_extension(&args.src, EF_EXTENSION).exists();
+
+    if let Some(range_size) = range_size {
+        // the statistics of each range are kept in memory
+        anyhow::ensure!(
+            num_nodes.div_ceil(range_size) <= MAX_RANGES,
+            "Too many ranges of nodes ({}), at most {} are supported: use a range size of at least {}",
+            num_nodes.div_ceil(range_size),
+            MAX_RANGES,
+            num_nodes.div_ceil(MAX_RANGES)
+        );
+        let ranges = if has_ef {
+            parallel_stats::<E>(&args, num_nodes, num_arcs, &flags, range_size)?
+        } else {
+            log::info!("The .ef file is missing, the graph will be scanned sequentially");
+            sequential_range_stats::<E>(&args, num_nodes, range_size)?
+        };
+        return print_ranges(&ranges, range_size, num_nodes, &flags);
+    }
+
+    let stats = if has_ef {
+        let mut stats = DecoderStatsAndCount::default();
+        for range_stats in
+            parallel_stats::<E>(&args, num_nodes, num_arcs, &flags, num_nodes.max(1))?
+        {
+            stats.add(&range_stats);
+        }
+        stats
+    } else {
+        log::info!("The .ef file is missing, the graph will be scanned sequentially");
+        sequential_stats::<E>(&args)?
+    };
+
+    let report = Report::new(&stats, &flags)?;
+
+    // the bit stream is padded to a whole number of words when it is written
+    let graph_path = append_extension(&args.src, GRAPH_EXTENSION);
+    let file_bits = 8 * std::fs::metadata(&graph_path)
+        .with_context(|| {
+            format!(
+                "Failed to read the size of {:?}",
+                graph_path.to_string_lossy()
+            )
+        })?
+        .len();
+    let total_bits = report.total_bits();
+    if total_bits > file_bits || file_bits - total_bits >= 64 {
+        log::warn!(
+            "The components add up to {} bits, but the .graph file has {} bits: the statistics or the compression flags are wrong",
+            total_bits,
+            file_bits
+        );
+    }
+
+    match args.format {
+        Format::Table => report.print_table(file_bits),
+        Format::Json => report.print_json(file_bits),
+        Format::Csv => report.print_csv(),
+    }
+    Ok(())
+}
+
+/// Collects the stats scanning the whole graph with a single decoder.
+fn sequential_stats<E: Endianness + 'static>(args: &CliArgs) -> Result<DecoderStatsAndCount>
+where
+    MmapHelper<u32>: CodesReaderFactoryHelper<E>,
+    for<'a> LoadModeCodesReader<'a, E, Mmap>: BitSeek,
+{
+    let graph = BvGraphSeq::with_basename(&args.src)
         .endianness::<E>()
         .load()?
         .map_factory(StatsAndCountDecoderFactory::new);
@@ -56,51 +174,468 @@ where
     pl.done();
 
     drop(iter); // This releases the decoder and updates the global stats
-    let stats = graph.into_inner().stats();
+    Ok(graph.into_inner().stats())
+}
 
-    macro_rules! impl_best_code {
-        ($old_bits:expr, $stats:expr, $($code:ident - $old:expr),*) => {
-            println!("{:>17} {:>16} {:>16} {:>16} {:>16} {:>12}",
-                "Type", "Bits", "Bytes", "Elements", "Average size", "Perc",
-            );
-            $(
-                $old_bits += $old;
-            )*
-
-            $(
-                println!("{:>17} {:>16} {:>16} {:>16} {:>16} {:>12}",
-                    stringify!($code),
-                    $old,
-                    $old / 8,
-                    $stats.$code.count,
-                    format!("{:.3}", $old as f64 / $stats.$code.count as f64),
-                    format!("{:.3}%", 100.0 * ($old) as f64 / $old_bits as f64),
-                );
-            )*
-        };
+/// Collects the stats of each range of `range_size` consecutive nodes scanning
+/// the whole graph with a single decoder, which tracks the node being decoded.
+fn sequential_range_stats<E: Endianness + 'static>(
+    args: &CliArgs,
+    num_nodes: usize,
+    range_size: usize,
+) -> Result<Vec<DecoderStatsAndCount>>
+where
+    MmapHelper<u32>: CodesReaderFactoryHelper<E>,
+    for<'a> LoadModeCodesReader<'a, E, Mmap>: BitSeek,
+{
+    let stats = (0..num_nodes.div_ceil(range_size))
+        .map(|_| RefCell::new(DecoderStatsAndCount::default()))
+        .collect::<Vec<_>>();
+    let graph = BvGraphSeq::with_basename(&args.src)
+        .endianness::<E>()
+        .load()?
+        .map_factory(|factory| {
+            ConsumerDecoderFactory::new(factory, |node, component, value| {
+                stats[node / range_size]
+                    .borrow_mut()
+                    .get_mut(component)
+                    .update(value);
+            })
+        });
+
+    let mut pl = ProgressLogger::default();
+    pl.display_memory(true)
+        .item_name("node")
+        .expected_updates(Some(num_nodes));
+
+    pl.start("Scanning...");
+
+    let mut iter = graph.iter();
+    while iter.next().is_some() {
+        pl.light_update();
     }
+    pl.done();
 
-    let mut old_bits = 0;
-    impl_best_code!(
-        old_bits,
-        stats,
-        outdegrees - stats.outdegrees.stats.gamma,
-        reference_offsets - stats.reference_offsets.stats.unary,
-        block_counts - stats.block_counts.stats.gamma,
-        blocks - stats.blocks.stats.gamma,
-        interval_counts - stats.interval_counts.stats.gamma,
-        interval_starts - stats.interval_starts.stats.gamma,
-        interval_lens - stats.interval_lens.stats.gamma,
-        first_residuals - stats.first_residuals.stats.zeta[2],
-        residuals - stats.residuals.stats.zeta[2]
-    );
+    drop(iter);
+    drop(graph);
+    Ok(stats.into_iter().map(RefCell::into_inner).collect())
+}
+
+/// The maximum number of ranges of nodes reported with `--buckets` or
+/// `--range-size`, bounding the memory used by their statistics.
+const MAX_RANGES: usize = 10_000;
+
+/// Collects the stats of each range of `range_size` consecutive nodes,
+/// splitting the ranges in chunks, which are scanned in parallel starting
+/// from their first node using the random-access graph.
+fn parallel_stats<E: Endianness + 'static>(
+    args: &CliArgs,
+    num_nodes: usize,
+    num_arcs: u64,
+    flags: &CompFlags,
+    range_size: usize,
+) -> Result<Vec<DecoderStatsAndCount>>
+where
+    MmapHelper<u32>: CodesReaderFactoryHelper<E>,
+    for<'a> LoadModeCodesReader<'a, E, Mmap>: BitSeek,
+{
+    let factory = BvGraph::with_basename(&args.src)
+        .endianness::<E>()
+        .load()?
+        .into_inner();
+
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads.unwrap_or(0))
+        .build()?;
+    let num_chunks = thread_pool.current_num_threads() * CHUNKS_PER_THREAD;
+    let chunk_size = num_nodes.div_ceil(num_chunks).max(1);
+
+    // the chunks never cross the boundary of a range
+    let ranges = (0..num_nodes)
+        .step_by(range_size)
+        .map(|start| start..(start + range_size).min(num_nodes))
+        .collect::<Vec<_>>();
+    let chunks = ranges
+        .iter()
+        .enumerate()
+        .flat_map(|(range_index, range)| {
+            range.clone().step_by(chunk_size).map(move |first_node| {
+                (
+                    range_index,
+                    first_node,
+                    chunk_size.min(range.end - first_node),
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+    let stats = ranges
+        .iter()
+        .map(|_| Mutex::new(DecoderStatsAndCount::default()))
+        .collect::<Vec<_>>();
+
+    let mut pl = ProgressLogger::default();
+    pl.display_memory(true)
+        .item_name("node")
+        .expected_updates(Some(num_nodes));
+    pl.start(format!(
+        "Scanning with {} threads...",
+        thread_pool.current_num_threads()
+    ));
+    let pl = Mutex::new(pl);
 
-    println!();
-    println!(" bit size: {:>16}", old_bits);
-    println!(" byte size: {:>16}", normalize(old_bits as f64 / 8.0));
+    thread_pool.install(|| {
+        chunks
+            .into_par_iter()
+            .for_each(|(range_index, first_node, len)| {
+                let graph = BvGraph::new(
+                    ChunkDecoderFactory::new(&factory, first_node, &stats[range_index]),
+                    num_nodes,
+                    num_arcs,
+                    flags.compression_window,
+                    flags.min_interval_length,
+                );
+                let mut iter = graph.iter_from(first_node).take(len);
+                while iter.next().is_some() {}
+                pl.lock().unwrap().update_with_count(len);
+            })
+    });
+    pl.into_inner().unwrap().done();
+
+    Ok(stats
+        .into_iter()
+        .map(|stats| stats.into_inner().unwrap())
+        .collect())
+}
+
+/// Prints as CSV the bits used by each component, with the codes of `flags`,
+/// in each range of `range_size` consecutive nodes.
+fn print_ranges(
+    ranges: &[DecoderStatsAndCount],
+    range_size: usize,
+    num_nodes: usize,
+    flags: &CompFlags,
+) -> Result<()> {
+    let components = BvGraphComponent::value_variants();
+    println!(
+        "start,end,{},total,bits_per_node",
+        components
+            .iter()
+            .map(|component| format!("{:?}", component))
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    for (range_index, stats) in ranges.iter().enumerate() {
+        let start = range_index * range_size;
+        let end = (start + range_size).min(num_nodes);
+        let mut bits = Vec::with_capacity(components.len());
+        for &component in components {
+            let code = component_code(flags, component);
+            bits.push(
+                code_bits(&stats.get(component).stats, code)
+                    .with_context(|| format!("Unsupported code {:?} for {}", code, component))?,
+            );
+        }
+        let total_bits = bits.iter().sum::<u64>();
+        println!(
+            "{},{},{},{},{:.3}",
+            start,
+            end,
+            bits.iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+            total_bits,
+            total_bits as f64 / (end - start) as f64
+        );
+    }
     Ok(())
 }
 
+/// The components sharing the same code in the compression flags, with the
+/// name of the corresponding option of `webgraph build`.
+const CODE_GROUPS: [(&str, &[BvGraphComponent]); 5] = [
+    ("outdegrees", &[BvGraphComponent::Outdegree]),
+    ("references", &[BvGraphComponent::ReferenceOffset]),
+    (
+        "blocks",
+        &[BvGraphComponent::BlockCount, BvGraphComponent::Blocks],
+    ),
+    (
+        "intervals",
+        &[
+            BvGraphComponent::IntervalCount,
+            BvGraphComponent::IntervalStart,
+            BvGraphComponent::IntervalLen,
+        ],
+    ),
+    (
+        "residuals",
+        &[BvGraphComponent::FirstResidual, BvGraphComponent::Residual],
+    ),
+];
+
+/// Returns the code used by `flags` for `component`.
+fn component_code(flags: &CompFlags, component: BvGraphComponent) -> Codes {
+    match component {
+        BvGraphComponent::Outdegree => flags.outdegrees,
+        BvGraphComponent::ReferenceOffset => flags.references,
+        BvGraphComponent::BlockCount | BvGraphComponent::Blocks => flags.blocks,
+        BvGraphComponent::IntervalCount
+        | BvGraphComponent::IntervalStart
+        | BvGraphComponent::IntervalLen => flags.intervals,
+        BvGraphComponent::FirstResidual | BvGraphComponent::Residual => flags.residuals,
+    }
+}
+
+/// Returns the bits needed to encode the values of `stats` with `code`, if
+/// the code is one of those that can be used to compress a BvGraph.
+fn code_bits(stats: &CodesStats, code: Codes) -> Option<u64> {
+    match code {
+        Codes::Unary => Some(stats.unary),
+        Codes::Gamma => Some(stats.gamma),
+        Codes::Delta => Some(stats.delta),
+        Codes::Zeta { k } => stats.zeta.get(k.checked_sub(1)?).copied(),
+        _ => None,
+    }
+}
+
+/// Returns the name of `code` on the command line.
+fn code_name(code: Code) -> String {
+    code.to_possible_value().unwrap().get_name().to_string()
+}
+
+/// The space used by a component of the graph.
+struct ComponentReport {
+    component: BvGraphComponent,
+    /// The code used to compress the component.
+    code: Codes,
+    /// The bits used by the component with `code`.
+    bits: u64,
+    /// The number of values of the component.
+    count: u64,
+    /// The optimal code for the component, among all the codes in [`CodesStats`].
+    optimal_code: Codes,
+    optimal_bits: u64,
+    /// The bits the component would use with each of the codes supported by BvGraph.
+    candidates: Vec<(Code, u64)>,
+}
+
+/// The space used by the components of a graph, and the codes that would minimize it.
+struct Report {
+    components: Vec<ComponentReport>,
+    /// The best option of `webgraph build` for each group of components.
+    best_options: Vec<(&'static str, Code)>,
+    /// The size of the graph compressed with the best options.
+    best_bits: u64,
+}
+
+impl Report {
+    fn new(stats: &DecoderStatsAndCount, flags: &CompFlags) -> Result<Self> {
+        let mut components = Vec::new();
+        for &component in BvGraphComponent::value_variants() {
+            let stats_with_count = stats.get(component);
+            let stats = &stats_with_count.stats;
+            let code = component_code(flags, component);
+            let bits = code_bits(stats, code)
+                .with_context(|| format!("Unsupported code {:?} for {}", code, component))?;
+            let (optimal_code, optimal_bits) = stats.best_code();
+            let candidates = Code::value_variants()
+                .iter()
+                .filter_map(|&candidate| Some((candidate, code_bits(stats, candidate.into())?)))
+                .collect();
+            components.push(ComponentReport {
+                component,
+                code,
+                bits,
+                count: stats_with_count.count,
+                optimal_code,
+                optimal_bits,
+                candidates,
+            });
+        }
+
+        // the components in a group must use the same code
+        let mut best_options = Vec::new();
+        let mut best_bits = 0;
+        for (option, group) in CODE_GROUPS {
+            let (best_code, bits) = Code::value_variants()
+                .iter()
+                .filter_map(|&code| {
+                    group
+                        .iter()
+                        .map(|&component| code_bits(&stats.get(component).stats, code.into()))
+                        .sum::<Option<u64>>()
+                        .map(|bits| (code, bits))
+                })
+                .min_by_key(|&(_, bits)| bits)
+                .context("No supported code")?;
+            best_options.push((option, best_code));
+            best_bits += bits;
+        }
+
+        Ok(Self {
+            components,
+            best_options,
+            best_bits,
+        })
+    }
+
+    fn total_bits(&self) -> u64 {
+        self.components.iter().map(|c| c.bits).sum()
+    }
+
+    /// The options of `webgraph build` selecting the best codes.
+    fn options(&self) -> String {
+        self.best_options
+            .iter()
+            .map(|&(option, code)| format!("--{} {}", option, code_name(code)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn print_table(&self, file_bits: u64) {
+        let total_bits = self.total_bits();
+        println!(
+            "{:>17} {:>16} {:>16} {:>16} {:>16} {:>16} {:>12}",
+            "Type", "Code", "Bits", "Bytes", "Elements", "Average size", "Perc",
+        );
+        for c in &self.components {
+            println!(
+                "{:>17} {:>16} {:>16} {:>16} {:>16} {:>16} {:>12}",
+                c.component.to_string(),
+                format!("{:?}", c.code),
+                c.bits,
+                c.bits / 8,
+                c.count,
+                format!("{:.3}", c.bits as f64 / c.count as f64),
+                format!("{:.3}%", percentage(c.bits, total_bits)),
+            );
+        }
+
+        println!();
+        println!(" bit size: {:>16}", total_bits);
+        println!(" byte size: {:>16}", normalize(total_bits as f64 / 8.0));
+        println!(" .graph file size: {:>16} bits", file_bits);
+
+        println!();
+        println!(
+            "{:>17} {:>16} {:>24} {:>16} {:>16}",
+            "Type", "Bits", "Optimal code", "Optimal bits", "Savings",
+        );
+        for c in &self.components {
+            println!(
+                "{:>17} {:>16} {:>24} {:>16} {:>16}",
+                c.component.to_string(),
+                c.bits,
+                format!("{:?}", c.optimal_code),
+                c.optimal_bits,
+                c.bits as i64 - c.optimal_bits as i64,
+            );
+        }
+
+        let savings = total_bits.saturating_sub(self.best_bits);
+        println!();
+        println!(" best codes (webgraph build options): {}", self.options());
+        println!(
+            " savings: {:>16} bits ({} bytes, {:.3}%)",
+            savings,
+            normalize(savings as f64 / 8.0),
+            percentage(savings, total_bits),
+        );
+    }
+
+    fn print_json(&self, file_bits: u64) {
+        let total_bits = self.total_bits();
+        let components = self
+            .components
+            .iter()
+            .map(|c| {
+                let candidates = c
+                    .candidates
+                    .iter()
+                    .map(|&(code, bits)| format!("\"{}\": {}", code_name(code), bits))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "    {{\"component\": \"{:?}\", \"code\": \"{:?}\", \"bits\": {}, \"bytes\": {}, \"count\": {}, \"average\": {}, \"percentage\": {}, \"optimal_code\": \"{:?}\", \"optimal_bits\": {}, \"candidates\": {{{}}}}}",
+                    c.component,
+                    c.code,
+                    c.bits,
+                    c.bits / 8,
+                    c.count,
+                    json_number(c.bits as f64 / c.count as f64),
+                    json_number(percentage(c.bits, total_bits)),
+                    c.optimal_code,
+                    c.optimal_bits,
+                    candidates
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",\n");
+        println!("{{");
+        println!("  \"components\": [\n{}\n  ],", components);
+        println!("  \"bits\": {},", total_bits);
+        println!("  \"file_bits\": {},", file_bits);
+        println!("  \"best_bits\": {},", self.best_bits);
+        println!("  \"best_options\": \"{}\"", self.options());
+        println!("}}");
+    }
+
+    fn print_csv(&self) {
+        let total_bits = self.total_bits();
+        let candidates = Code::value_variants()
+            .iter()
+            .map(|&code| code_name(code))
+            .collect::<Vec<_>>()
+            .join(",");
+        println!(
+            "component,code,bits,bytes,count,average,percentage,optimal_code,optimal_bits,{}",
+            candidates
+        );
+        for c in &self.components {
+            let candidates = c
+                .candidates
+                .iter()
+                .map(|&(_, bits)| bits.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            println!(
+                "{:?},\"{:?}\",{},{},{},{:.3},{:.3},\"{:?}\",{},{}",
+                c.component,
+                c.code,
+                c.bits,
+                c.bits / 8,
+                c.count,
+                c.bits as f64 / c.count as f64,
+                percentage(c.bits, total_bits),
+                c.optimal_code,
+                c.optimal_bits,
+                candidates
+            );
+        }
+    }
+}
+
+/// Returns the percentage of `bits` over `total_bits`, or zero if the total is
+/// zero (i.e., the graph is empty).
+fn percentage(bits: u64, total_bits: u64) -> f64 {
+    if total_bits == 0 {
+        0.0
+    } else {
+        100.0 * bits as f64 / total_bits as f64
+    }
+}
+
+/// Formats a float as a JSON number, using null for the values (NaN, infinities)
+/// JSON can not represent, e.g., the average of an empty component.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:.3}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
 fn normalize(mut value: f64) -> String {
     let mut uom = ' ';
     if value > 1000.0 {
@@ -1,13 +1,14 @@
 use anyhow::Result;
 use clap::{ArgMatches, Command};
 
+pub mod costly;
 mod dec_stats_and_count;
 pub mod dissect;
 pub mod print;
 use dec_stats_and_count::*;
-mod component;
+pub(crate) mod component;
 mod consume_decoder;
-use consume_decoder::*;
+pub(crate) use consume_decoder::*;
 
 pub const COMMAND_NAME: &str = "analyze";
 
@@ -18,6 +19,7 @@ pub fn cli(command: Command) -> Command {
         .arg_required_else_help(true)
         .allow_external_subcommands(true);
     let sub_command = dissect::cli(sub_command);
+    let sub_command = costly::cli(sub_command);
     let sub_command = print::cli(sub_command);
     command.subcommand(sub_command.display_order(0))
 }
@@ -26,6 +28,7 @@ pub fn main(submatches: &ArgMatches) -> Result<()> {
     match submatches.subcommand() {
         Some((dissect::COMMAND_NAME, sub_m)) => dissect::main(sub_m),
         Some((print::COMMAND_NAME, sub_m)) => print::main(sub_m),
+        Some((costly::COMMAND_NAME, sub_m)) => costly::main(sub_m),
         Some((command_name, _)) => {
             eprintln!("Unknown command: {:?}", command_name);
             std::process::exit(1);
@@ -1,22 +1,111 @@
-use anyhow::Result;
-use clap::{ArgMatches, Args, Command, FromArgMatches};
+use anyhow::{Context, Result};
+use clap::{ArgAction, ArgMatches, Args, Command, FromArgMatches, ValueEnum};
 use dsi_bitstream::{dispatch::factory::CodesReaderFactoryHelper, prelude::*};
 use dsi_progress_logger::prelude::*;
 use lender::*;
-use std::path::PathBuf;
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
 use webgraph::prelude::*;
 
-use crate::analyze::{component::BvGraphComponent, ConsumerDecoderFactory};
+use crate::{
+    analyze::{component::BvGraphComponent, ConsumerDecoderFactory},
+    dispatch_endianness,
+    npy::{Dtype, NpyWriter},
+    utils::create_parent_dir,
+};
 
 pub const COMMAND_NAME: &str = "print";
 
 #[derive(Args, Debug)]
-#[command(about = "Prints the decoded instantaneous codes from the stream to stdout. Usefull to inspect the distribution of gaps or any other component", long_about = None)]
+#[command(
+    about = "Prints the decoded instantaneous codes from the stream to stdout. Usefull to inspect the distribution of gaps or any other component",
+    long_about = "Prints the decoded instantaneous codes of one or more components from the stream to stdout (or to a file with --output), one per line. Usefull to inspect the distribution of gaps or any other component. If more than one component is printed, or with --with-node, each line starts with the name of the component of the value, and with --with-node it starts with the node whose record contains it, so values of different components can be correlated; the columns are separated by tabs, or written as binary integers (the component as its index) with --binary, or in a .npy file with --npy.",
+    allow_missing_positional = true
+)]
 pub struct CliArgs {
-    /// The component to print
-    pub component: BvGraphComponent,
+    /// The components to print, separated by commas.
+    #[arg(
+        value_name = "COMPONENTS",
+        value_delimiter = ',',
+        action = ArgAction::Set,
+        required_unless_present = "components",
+        conflicts_with = "components"
+    )]
+    pub positional_components: Vec<BvGraphComponent>,
     /// The basename of the graph.
     pub src: PathBuf,
+
+    /// The components to print, separated by commas (the same as the
+    /// positional argument).
+    #[arg(long, value_delimiter = ',', action = ArgAction::Set)]
+    pub components: Vec<BvGraphComponent>,
+
+    /// Instead of printing each value, count the values in memory and print a
+    /// histogram as "min\tmax\tcount" lines: the values smaller than `max_value`
+    /// are counted exactly, the others in buckets [2^k..2^(k+1)).
+    #[arg(long)]
+    pub histogram: bool,
+
+    /// Count all the values of the histogram in buckets [2^k..2^(k+1)), with
+    /// zero in a bucket of its own.
+    #[arg(long, requires = "histogram", conflicts_with = "max_value")]
+    pub log_buckets: bool,
+
+    /// The values of the histogram smaller than this (at most 2^20) are counted
+    /// exactly.
+    #[arg(
+        long,
+        requires = "histogram",
+        default_value_t = 1024,
+        value_parser = clap::value_parser!(u64).range(..=MAX_EXACT_VALUE)
+    )]
+    pub max_value: u64,
+
+    /// Print the node whose record contains each value, and its component.
+    #[arg(long, conflicts_with = "histogram")]
+    pub with_node: bool,
+
+    /// Write the output to this file instead of stdout.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Write each column as a binary integer instead of text.
+    #[arg(long, value_enum, conflicts_with = "histogram")]
+    pub binary: Option<BinaryFormat>,
+
+    /// Write the output as a .npy file of unsigned integers of the given width,
+    /// with a column for each column of the output.
+    #[arg(
+        long,
+        value_enum,
+        requires = "output",
+        conflicts_with_all = ["histogram", "binary"]
+    )]
+    pub npy: Option<NpyFormat>,
+}
+
+/// The encodings of the columns of the binary output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum BinaryFormat {
+    /// Little-endian 32-bit integers.
+    U32,
+    /// Little-endian 64-bit integers.
+    U64,
+    /// LEB128 variable-length integers, with 7 bits per byte.
+    Varint,
+}
+
+/// The data types of the .npy output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum NpyFormat {
+    /// Unsigned 32-bit integers.
+    U32,
+    /// Unsigned 64-bit integers.
+    U64,
 }
 
 pub fn cli(command: Command) -> Command {
@@ -26,11 +115,7 @@ pub fn cli(command: Command) -> Command {
 pub fn main(submatches: &ArgMatches) -> Result<()> {
     let args = CliArgs::from_arg_matches(submatches)?;
 
-    match get_endianness(&args.src)?.as_str() {
-        BE::NAME => log_graph::<BE>(args),
-        LE::NAME => log_graph::<LE>(args),
-        e => panic!("Unknown endianness: {}", e),
-    }
+    dispatch_endianness!(&args.src, log_graph(args))
 }
 
 pub fn log_graph<E: Endianness + 'static>(args: CliArgs) -> Result<()>
@@ -38,14 +123,74 @@ where
     MmapHelper<u32>: CodesReaderFactoryHelper<E>,
     for<'a> LoadModeCodesReader<'a, E, Mmap>: BitSeek,
 {
-    let target_component = args.component;
-    let graph = BvGraphSeq::with_basename(args.src)
+    let mut selected = [false; BvGraphComponent::COMPONENTS];
+    for &component in args.components.iter().chain(&args.positional_components) {
+        selected[component as usize] = true;
+    }
+    // the rows have the same layout whenever the node is printed
+    let with_component =
+        args.with_node || selected.iter().filter(|&&selected| selected).count() > 1;
+
+    let histograms = RefCell::new(
+        selected
+            .iter()
+            .map(|&selected| {
+                Histogram::new(if args.histogram && selected && !args.log_buckets {
+                    args.max_value
+                } else {
+                    0
+                })
+            })
+            .collect::<Vec<_>>(),
+    );
+    let columns = 1 + args.with_node as usize + with_component as usize;
+    let sink = if let Some(format) = args.npy {
+        let dtype = match format {
+            NpyFormat::U32 => Dtype::Uint32,
+            NpyFormat::U64 => Dtype::Uint64,
+        };
+        // required by clap
+        let path = args.output.as_ref().unwrap();
+        create_parent_dir(path)?;
+        let npy = NpyWriter::create(path, dtype)?;
+        Sink::Npy(if columns > 1 {
+            npy.with_columns(columns)?
+        } else {
+            npy
+        })
+    } else {
+        let writer: Box<dyn Write> = match &args.output {
+            Some(path) => {
+                create_parent_dir(path)?;
+                Box::new(File::create(path).with_context(|| {
+                    format!("Failed to create the file {:?}", path.to_string_lossy())
+                })?)
+            }
+            None => Box::new(std::io::stdout().lock()),
+        };
+        let writer = BufWriter::new(writer);
+        match args.binary {
+            Some(format) => Sink::Binary(writer, format),
+            None => Sink::Text(writer),
+        }
+    };
+    let rows = RefCell::new(RowWriter {
+        sink,
+        with_node: args.with_node,
+        with_component,
+        error: None,
+    });
+    let graph = BvGraphSeq::with_basename(&args.src)
         .endianness::<E>()
         .load()?
         .map_factory(|factory| {
-            ConsumerDecoderFactory::new(factory, move |component, value| {
-                if component == target_component {
-                    println!("{}", value)
+            ConsumerDecoderFactory::new(factory, |node, component, value| {
+                if selected[component as usize] {
+                    if args.histogram {
+                        histograms.borrow_mut()[component as usize].add(value);
+                    } else {
+                        rows.borrow_mut().write(node, component, value);
+                    }
                 }
             })
         });
@@ -60,8 +205,282 @@ where
     let mut iter = graph.iter();
     while iter.next().is_some() {
         pl.light_update();
+        // stop at the first write error, which is returned by finish
+        if rows.borrow().error.is_some() {
+            break;
+        }
     }
     pl.done();
+    drop(iter);
+    drop(graph);
 
+    let mut rows = rows.into_inner();
+    if args.histogram {
+        // the binary formats conflict with the histogram
+        let Sink::Text(writer) = &mut rows.sink else {
+            unreachable!()
+        };
+        if with_component {
+            write!(writer, "component\t")?;
+        }
+        writeln!(writer, "min\tmax\tcount")?;
+        for &component in BvGraphComponent::value_variants() {
+            if !selected[component as usize] {
+                continue;
+            }
+            for (min, max, count) in histograms.borrow()[component as usize].buckets() {
+                if with_component {
+                    write!(writer, "{}\t", component_name(component))?;
+                }
+                writeln!(writer, "{}\t{}\t{}", min, max, count)?;
+            }
+        }
+    }
+    rows.finish()
+}
+
+/// Returns the name of `component` on the command line.
+fn component_name(component: BvGraphComponent) -> &'static str {
+    match component {
+        BvGraphComponent::Outdegree => "outdegree",
+        BvGraphComponent::ReferenceOffset => "reference-offset",
+        BvGraphComponent::BlockCount => "block-count",
+        BvGraphComponent::Blocks => "blocks",
+        BvGraphComponent::IntervalCount => "interval-count",
+        BvGraphComponent::IntervalStart => "interval-start",
+        BvGraphComponent::IntervalLen => "interval-len",
+        BvGraphComponent::FirstResidual => "first-residual",
+        BvGraphComponent::Residual => "residual",
+    }
+}
+
+/// Where the decoded values are written.
+enum Sink {
+    Text(BufWriter<Box<dyn Write>>),
+    Binary(BufWriter<Box<dyn Write>>, BinaryFormat),
+    Npy(NpyWriter<BufWriter<File>>),
+}
+
+/// Writes the decoded values, with their node and component if requested.
+struct RowWriter {
+    sink: Sink,
+    with_node: bool,
+    with_component: bool,
+    /// The first error, after which nothing is written: the values are
+    /// written by the decoder, which can not return errors.
+    error: Option<anyhow::Error>,
+}
+
+impl RowWriter {
+    #[inline(always)]
+    fn write(&mut self, node: usize, component: BvGraphComponent, value: u64) {
+        if self.error.is_none() {
+            if let Err(error) = self.write_row(node, component, value) {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    fn write_row(&mut self, node: usize, component: BvGraphComponent, value: u64) -> Result<()> {
+        let columns = [
+            self.with_node.then_some(node as u64),
+            self.with_component.then_some(component as u64),
+            Some(value),
+        ];
+        match &mut self.sink {
+            Sink::Text(writer) => {
+                if self.with_node {
+                    write!(writer, "{}\t", node)?;
+                }
+                if self.with_component {
+                    write!(writer, "{}\t", component_name(component))?;
+                }
+                writeln!(writer, "{}", value)?;
+            }
+            Sink::Binary(writer, format) => {
+                for column in columns.into_iter().flatten() {
+                    write_binary(writer, *format, column)?;
+                }
+            }
+            Sink::Npy(npy) => {
+                for column in columns.into_iter().flatten() {
+                    npy.push(column)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Flushes the output, returning the first error.
+    fn finish(mut self) -> Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error).context("Failed to write the values");
+        }
+        match self.sink {
+            Sink::Text(mut writer) | Sink::Binary(mut writer, _) => {
+                writer.flush().context("Failed to flush the output")
+            }
+            Sink::Npy(npy) => npy.finish().map(|_| ()),
+        }
+    }
+}
+
+/// Writes `value` with the given binary format.
+#[inline(always)]
+fn write_binary(writer: &mut impl Write, format: BinaryFormat, mut value: u64) -> Result<()> {
+    match format {
+        BinaryFormat::U32 => {
+            let value = u32::try_from(value)
+                .with_context(|| format!("Value {} does not fit in 32 bits", value))?;
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        BinaryFormat::U64 => writer.write_all(&value.to_le_bytes())?,
+        BinaryFormat::Varint => {
+            while value >= 0x80 {
+                writer.write_all(&[(value as u8 & 0x7f) | 0x80])?;
+                value >>= 7;
+            }
+            writer.write_all(&[value as u8])?;
+        }
+    }
     Ok(())
 }
+
+/// The largest threshold of the values counted exactly by a [`Histogram`],
+/// which keeps a counter for each of them.
+const MAX_EXACT_VALUE: u64 = 1 << 20;
+
+/// The counts of the values of a component: exact below a threshold, and in
+/// buckets of exponentially increasing size above it.
+struct Histogram {
+    /// The counts of the values smaller than its length.
+    exact: Vec<u64>,
+    /// The counts of the other values: the bucket `k` contains the values with
+    /// `k` significant bits, i.e., zero for `k` = 0 and [2^(k-1)..2^k) otherwise.
+    log: [u64; 65],
+}
+
+impl Histogram {
+    fn new(max_value: u64) -> Self {
+        Self {
+            exact: vec![0; max_value as usize],
+            log: [0; 65],
+        }
+    }
+
+    #[inline(always)]
+    fn add(&mut self, value: u64) {
+        match self.exact.get_mut(value as usize) {
+            Some(count) => *count += 1,
+            None => self.log[(u64::BITS - value.leading_zeros()) as usize] += 1,
+        }
+    }
+
+    /// Returns the non-empty buckets as (min, max, count) triples.
+    fn buckets(&self) -> Vec<(u64, u64, u64)> {
+        let mut buckets = Vec::new();
+        for (value, &count) in self.exact.iter().enumerate() {
+            if count > 0 {
+                buckets.push((value as u64, value as u64, count));
+            }
+        }
+        let max_value = self.exact.len() as u64;
+        for (bits, &count) in self.log.iter().enumerate() {
+            if count > 0 {
+                let (min, max) = match bits {
+                    0 => (0, 0),
+                    _ => (1 << (bits - 1), u64::MAX >> (u64::BITS as usize - bits)),
+                };
+                // the first bucket can contain also values counted exactly
+                buckets.push((min.max(max_value), max, count));
+            }
+        }
+        buckets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buckets(max_value: u64, values: &[u64]) -> Vec<(u64, u64, u64)> {
+        let mut histogram = Histogram::new(max_value);
+        for &value in values {
+            histogram.add(value);
+        }
+        histogram.buckets()
+    }
+
+    #[test]
+    fn test_log_buckets() {
+        assert_eq!(
+            buckets(0, &[0, 0, 1, 2, 3, 4, u64::MAX]),
+            [
+                (0, 0, 2),
+                (1, 1, 1),
+                (2, 3, 2),
+                (4, 7, 1),
+                (1 << 63, u64::MAX, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exact_buckets() {
+        // the bucket of 1000 starts at 512, but smaller values are exact
+        assert_eq!(
+            buckets(1000, &[0, 999, 1000, 1023, 1024, u64::MAX]),
+            [
+                (0, 0, 1),
+                (999, 999, 1),
+                (1000, 1023, 2),
+                (1024, 2047, 1),
+                (1 << 63, u64::MAX, 1)
+            ]
+        );
+        assert_eq!(
+            buckets(1024, &[1023, 1024, 2047, 2048]),
+            [(1023, 1023, 1), (1024, 2047, 2), (2048, 4095, 1)]
+        );
+        assert!(buckets(1024, &[]).is_empty());
+    }
+
+    fn varint(value: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_binary(&mut bytes, BinaryFormat::Varint, value).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_varint() {
+        assert_eq!(varint(0), [0x00]);
+        assert_eq!(varint(0x7f), [0x7f]);
+        assert_eq!(varint(0x80), [0x80, 0x01]);
+        assert_eq!(varint(300), [0xac, 0x02]);
+        assert_eq!(varint(0x3fff), [0xff, 0x7f]);
+        assert_eq!(varint(0x4000), [0x80, 0x80, 0x01]);
+        assert_eq!(
+            varint(u64::MAX),
+            [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]
+        );
+    }
+
+    #[test]
+    fn test_fixed_width() {
+        let mut bytes = Vec::new();
+        write_binary(&mut bytes, BinaryFormat::U32, u32::MAX as u64).unwrap();
+        write_binary(&mut bytes, BinaryFormat::U64, 1).unwrap();
+        assert_eq!(bytes, [0xff, 0xff, 0xff, 0xff, 1, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(write_binary(&mut bytes, BinaryFormat::U32, 1 << 32).is_err());
+    }
+
+    #[test]
+    fn test_component_names() {
+        for &component in BvGraphComponent::value_variants() {
+            assert_eq!(
+                component_name(component),
+                component.to_possible_value().unwrap().get_name()
+            );
+        }
+    }
+}
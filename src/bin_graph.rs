@@ -0,0 +1,561 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use mmap_rs::{Mmap, MmapOptions};
+use std::{
+    fs::File,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+use webgraph::prelude::*;
+
+// Simple on-disk representation of a graph that can directly mapped into memory
+// (allowing reduced memory usage).
+// Format description:
+// - 8 bytes of fingerprint
+// - W bytes to represent the number of nodes N
+// - N+1 8-byte integers that represent the index of the first edge of the i-th
+//   adjacency list. The last of these integers is the total number of edges, M.
+// - M W-byte integers that represent the destination node of each graph edge.
+// All the integers are written in little endian, and the width W of the node
+// identifiers is either 4 (the format used by Zuckerli) or 8 bytes.
+
+/// The width of the node identifiers (and of the number of nodes) in the bin format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum NodeWidth {
+    /// 4-byte node identifiers, as used by Zuckerli.
+    U32,
+    /// 8-byte node identifiers, for graphs with more than 2^32 - 1 nodes.
+    U64,
+}
+
+impl NodeWidth {
+    /// Returns the narrowest width that can represent a graph with `num_nodes` nodes.
+    pub fn for_num_nodes(num_nodes: usize) -> Self {
+        if num_nodes as u64 > u32::MAX as u64 {
+            NodeWidth::U64
+        } else {
+            NodeWidth::U32
+        }
+    }
+
+    /// Returns an error if a graph with `num_nodes` nodes can not be
+    /// represented with this width.
+    pub fn check(self, num_nodes: usize) -> Result<()> {
+        anyhow::ensure!(
+            self == NodeWidth::U64 || num_nodes as u64 <= u32::MAX as u64,
+            "A graph with {} nodes can not be represented with {:?} node identifiers, use the wide (u64) format",
+            num_nodes,
+            self
+        );
+        Ok(())
+    }
+
+    /// Returns `width`, if given, checking that it can represent a graph with
+    /// `num_nodes` nodes, or the narrowest width that can represent it.
+    pub fn resolve(width: Option<NodeWidth>, num_nodes: usize) -> Result<Self> {
+        match width {
+            Some(width) => {
+                width.check(num_nodes)?;
+                Ok(width)
+            }
+            None => Ok(NodeWidth::for_num_nodes(num_nodes)),
+        }
+    }
+
+    /// The number of bytes used to represent a node.
+    #[inline(always)]
+    pub fn bytes(self) -> usize {
+        match self {
+            NodeWidth::U32 => std::mem::size_of::<u32>(),
+            NodeWidth::U64 => std::mem::size_of::<u64>(),
+        }
+    }
+
+    /// Returns the fingerprint of the bin format: number of bytes to represent the
+    /// number of edges followed by number of bytes to represent the number of nodes.
+    pub fn fingerprint(self) -> u64 {
+        (std::mem::size_of::<u64>() as u64) << 4 | self.bytes() as u64
+    }
+
+    /// Returns the width whose fingerprint is `fingerprint`.
+    pub fn from_fingerprint(fingerprint: u64) -> Result<Self> {
+        [NodeWidth::U32, NodeWidth::U64]
+            .into_iter()
+            .find(|width| width.fingerprint() == fingerprint)
+            .with_context(|| {
+                format!(
+                    "Invalid fingerprint: expected {} or {}, got {}",
+                    NodeWidth::U32.fingerprint(),
+                    NodeWidth::U64.fingerprint(),
+                    fingerprint
+                )
+            })
+    }
+
+    /// The length in bytes of the header: the fingerprint followed by the number of nodes.
+    pub fn header_len(self) -> usize {
+        8 + self.bytes()
+    }
+
+    /// Writes `node` in little endian using this width.
+    ///
+    /// Fails instead of truncating if `node` does not fit.
+    #[inline(always)]
+    pub fn write_node(self, writer: &mut impl Write, node: usize) -> Result<()> {
+        match self {
+            NodeWidth::U32 => {
+                let node = u32::try_from(node).with_context(|| {
+                    format!("Node {} does not fit in a u32 node identifier", node)
+                })?;
+                writer.write_all(&node.to_le_bytes())?
+            }
+            NodeWidth::U64 => writer.write_all(&(node as u64).to_le_bytes())?,
+        }
+        Ok(())
+    }
+
+    /// Reads a little-endian node from `bytes`, which must be exactly
+    /// [`bytes`](Self::bytes) long.
+    #[inline(always)]
+    pub fn read_node(self, bytes: &[u8]) -> usize {
+        match self {
+            NodeWidth::U32 => u32::from_le_bytes(bytes.try_into().unwrap()) as usize,
+            NodeWidth::U64 => u64::from_le_bytes(bytes.try_into().unwrap()) as usize,
+        }
+    }
+}
+
+/// The header of a graph in the bin format, with the number of arcs read from
+/// the last offset.
+#[derive(Clone, Copy, Debug)]
+pub struct BinHeader {
+    pub width: NodeWidth,
+    pub num_nodes: usize,
+    pub num_arcs: u64,
+}
+
+impl BinHeader {
+    /// Reads the header of a bin file of `file_len` bytes, checking that the size
+    /// of the file is consistent with it and that the first offset is zero.
+    ///
+    /// The position of `reader` after the call is unspecified.
+    pub fn read(reader: &mut (impl Read + Seek), file_len: u64) -> Result<Self> {
+        let width = NodeWidth::from_fingerprint(
+            read_u64(reader).context("Failed to read the fingerprint")?,
+        )?;
+        let mut num_nodes_bytes = [0u8; 8];
+        reader
+            .read_exact(&mut num_nodes_bytes[..width.bytes()])
+            .context("Failed to read the number of nodes")?;
+        let num_nodes = width.read_node(&num_nodes_bytes[..width.bytes()]);
+
+        let arcs_start = (num_nodes as u64)
+            .checked_add(1)
+            .and_then(|offsets| offsets.checked_mul(8))
+            .and_then(|len| len.checked_add(width.header_len() as u64))
+            .filter(|&arcs_start| arcs_start <= file_len)
+            .with_context(|| {
+                format!(
+                    "Invalid file length: {} bytes can not contain the offsets of {} nodes",
+                    file_len, num_nodes
+                )
+            })?;
+        // The last of the N+1 offsets is the total number of arcs
+        reader.seek(SeekFrom::Start(arcs_start - 8))?;
+        let num_arcs = read_u64(reader).context("Failed to read the number of arcs")?;
+        let expected_len = (width.bytes() as u64)
+            .checked_mul(num_arcs)
+            .and_then(|len| len.checked_add(arcs_start));
+        anyhow::ensure!(
+            expected_len == Some(file_len),
+            "Invalid file length: {} bytes can not contain {} nodes and {} arcs",
+            file_len,
+            num_nodes,
+            num_arcs
+        );
+
+        reader.seek(SeekFrom::Start(width.header_len() as u64))?;
+        let first_offset = read_u64(reader).context("Failed to read the first offset")?;
+        anyhow::ensure!(
+            first_offset == 0,
+            "Invalid first offset: expected 0, got {}",
+            first_offset
+        );
+
+        Ok(Self {
+            width,
+            num_nodes,
+            num_arcs,
+        })
+    }
+
+    /// The position in the file of the first offset.
+    pub fn offsets_start(&self) -> u64 {
+        self.width.header_len() as u64
+    }
+
+    /// The position in the file of the first arc.
+    pub fn arcs_start(&self) -> u64 {
+        // it does not overflow, as the size of the file has been checked
+        self.offsets_start() + 8 * (self.num_nodes as u64 + 1)
+    }
+}
+
+/// Reads a little-endian u64.
+pub fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Returns true if `path` is a file starting with the fingerprint of the bin format.
+///
+/// Commands accepting both a BvGraph basename and a bin file can use this
+/// function to choose how to load the graph.
+pub fn is_bin_graph(path: impl AsRef<Path>) -> bool {
+    let mut fingerprint_bytes = [0u8; 8];
+    File::open(path.as_ref())
+        .and_then(|mut file| file.read_exact(&mut fingerprint_bytes))
+        .is_ok()
+        && NodeWidth::from_fingerprint(u64::from_le_bytes(fingerprint_bytes)).is_ok()
+}
+
+/// A graph in the bin format memory-mapped from disk.
+///
+/// Nothing is loaded in memory: offsets and arcs are decoded on the fly from
+/// the mapped file, so the graph can be accessed randomly through the webgraph
+/// traits regardless of its size.
+pub struct BinGraphMmap {
+    mmap: Mmap,
+    width: NodeWidth,
+    num_nodes: usize,
+    num_arcs: u64,
+    arcs_start: usize,
+}
+
+impl BinGraphMmap {
+    /// Memory-maps the graph at `path`, checking that the header and the size of
+    /// the file are consistent, that the offsets are non-decreasing and that the
+    /// arc destinations are valid nodes, so that the successors can then be
+    /// accessed without further checks.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open file {:?}", path.to_string_lossy()))?;
+        let file_len = file.metadata()?.len();
+        anyhow::ensure!(
+            file_len >= 8,
+            "File {:?} is too short to be a bin graph ({} bytes)",
+            path.to_string_lossy(),
+            file_len
+        );
+        let mmap = unsafe {
+            MmapOptions::new(file_len as usize)?
+                .with_file(&file, 0)
+                .map()
+        }
+        .with_context(|| format!("Failed to mmap file {:?}", path.to_string_lossy()))?;
+
+        let header = BinHeader::read(&mut Cursor::new(&mmap[..]), file_len)
+            .with_context(|| format!("Invalid bin graph {:?}", path.to_string_lossy()))?;
+        let graph = BinGraphMmap {
+            mmap,
+            width: header.width,
+            num_nodes: header.num_nodes,
+            num_arcs: header.num_arcs,
+            arcs_start: header.arcs_start() as usize,
+        };
+
+        // the first offset is zero and the last one is the number of arcs, so
+        // non-decreasing offsets are all within the arcs
+        let mut offset = 0;
+        for node in 0..graph.num_nodes {
+            let next_offset = graph.offset(node + 1);
+            anyhow::ensure!(
+                offset <= next_offset,
+                "Invalid offsets for node {}: {}..{}",
+                node,
+                offset,
+                next_offset
+            );
+            offset = next_offset;
+        }
+
+        let bytes = graph.width.bytes();
+        if let Some(arc) = graph.mmap[graph.arcs_start..]
+            .chunks_exact(bytes)
+            .position(|arc| graph.width.read_node(arc) >= graph.num_nodes)
+        {
+            anyhow::bail!(
+                "Invalid destination of arc {}: {} >= number of nodes ({})",
+                arc,
+                graph
+                    .width
+                    .read_node(&graph.mmap[graph.arcs_start + arc * bytes..][..bytes]),
+                graph.num_nodes
+            );
+        }
+        Ok(graph)
+    }
+
+    /// Returns the width of the node identifiers of the graph.
+    pub fn width(&self) -> NodeWidth {
+        self.width
+    }
+
+    /// Returns the index of the first arc of `node`.
+    #[inline(always)]
+    fn offset(&self, node: usize) -> u64 {
+        let start = self.width.header_len() + 8 * node;
+        u64::from_le_bytes(self.mmap[start..start + 8].try_into().unwrap())
+    }
+}
+
+impl SequentialLabeling for BinGraphMmap {
+    type Label = usize;
+    type Lender<'node>
+        = IteratorImpl<'node, Self>
+    where
+        Self: 'node;
+
+    #[inline(always)]
+    fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    #[inline(always)]
+    fn num_arcs_hint(&self) -> Option<u64> {
+        Some(self.num_arcs)
+    }
+
+    #[inline(always)]
+    fn iter_from(&self, from: usize) -> Self::Lender<'_> {
+        IteratorImpl {
+            labeling: self,
+            nodes: from..self.num_nodes,
+        }
+    }
+}
+
+impl SequentialGraph for BinGraphMmap {}
+
+impl RandomAccessLabeling for BinGraphMmap {
+    type Labels<'succ>
+        = Successors<'succ>
+    where
+        Self: 'succ;
+
+    #[inline(always)]
+    fn num_arcs(&self) -> u64 {
+        self.num_arcs
+    }
+
+    #[inline(always)]
+    fn outdegree(&self, node: usize) -> usize {
+        (self.offset(node + 1) - self.offset(node)) as usize
+    }
+
+    #[inline(always)]
+    fn labels(&self, node: usize) -> Self::Labels<'_> {
+        let bytes = self.width.bytes();
+        let start = self.arcs_start + bytes * self.offset(node) as usize;
+        let end = self.arcs_start + bytes * self.offset(node + 1) as usize;
+        Successors {
+            width: self.width,
+            arcs: self.mmap[start..end].chunks_exact(bytes),
+        }
+    }
+}
+
+impl RandomAccessGraph for BinGraphMmap {}
+
+/// The successors of a node of a [`BinGraphMmap`], decoded from the mapped
+/// little-endian arcs.
+pub struct Successors<'a> {
+    width: NodeWidth,
+    arcs: std::slice::ChunksExact<'a, u8>,
+}
+
+impl Iterator for Successors<'_> {
+    type Item = usize;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<usize> {
+        self.arcs.next().map(|arc| self.width.read_node(arc))
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.arcs.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Successors<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lender::*;
+
+    /// Returns a bin file with the given (raw) number of nodes, offsets and arcs.
+    fn bin_file(width: NodeWidth, num_nodes: u64, offsets: &[u64], arcs: &[u64]) -> Vec<u8> {
+        let mut bytes = width.fingerprint().to_le_bytes().to_vec();
+        bytes.extend_from_slice(&num_nodes.to_le_bytes()[..width.bytes()]);
+        for offset in offsets {
+            bytes.extend_from_slice(&offset.to_le_bytes());
+        }
+        for arc in arcs {
+            bytes.extend_from_slice(&arc.to_le_bytes()[..width.bytes()]);
+        }
+        bytes
+    }
+
+    fn read_header(bytes: &[u8]) -> Result<BinHeader> {
+        BinHeader::read(&mut Cursor::new(bytes), bytes.len() as u64)
+    }
+
+    /// The graph 0 -> 1, 0 -> 2, 2 -> 0.
+    fn small_graph(width: NodeWidth) -> Vec<u8> {
+        bin_file(width, 3, &[0, 2, 2, 3], &[1, 2, 0])
+    }
+
+    #[test]
+    fn test_header() -> Result<()> {
+        for width in [NodeWidth::U32, NodeWidth::U64] {
+            let header = read_header(&small_graph(width))?;
+            assert_eq!(header.width, width);
+            assert_eq!(header.num_nodes, 3);
+            assert_eq!(header.num_arcs, 3);
+            assert_eq!(header.offsets_start(), 8 + width.bytes() as u64);
+            assert_eq!(header.arcs_start(), header.offsets_start() + 4 * 8);
+        }
+        // the empty graph has only the first offset
+        let header = read_header(&bin_file(NodeWidth::U32, 0, &[0], &[]))?;
+        assert_eq!((header.num_nodes, header.num_arcs), (0, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncated() {
+        let bytes = small_graph(NodeWidth::U32);
+        for len in [0, 4, 8, 10, bytes.len() - 1] {
+            assert!(read_header(&bytes[..len]).is_err(), "length {}", len);
+        }
+        let mut bytes = bytes;
+        bytes.push(0);
+        assert!(read_header(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_wrong_fingerprint() {
+        let mut bytes = small_graph(NodeWidth::U32);
+        bytes[0] ^= 1;
+        assert!(read_header(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_nonzero_first_offset() {
+        let bytes = bin_file(NodeWidth::U32, 2, &[1, 1, 2], &[1, 0]);
+        assert!(read_header(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_length_overflow() {
+        // the offsets of these graphs would not fit in a u64 file length
+        for num_nodes in [u64::MAX, u64::MAX / 8, u64::MAX / 8 - 1] {
+            let bytes = bin_file(NodeWidth::U64, num_nodes, &[0], &[]);
+            assert!(read_header(&bytes).is_err(), "{} nodes", num_nodes);
+        }
+        // the arcs would not fit in a u64 file length
+        let bytes = bin_file(NodeWidth::U64, 1, &[0, u64::MAX / 4], &[]);
+        assert!(read_header(&bytes).is_err());
+    }
+
+    /// Writes `bytes` to a temporary file and memory-maps it.
+    fn load(bytes: &[u8]) -> Result<BinGraphMmap> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("graph.bin");
+        std::fs::write(&path, bytes)?;
+        BinGraphMmap::load(&path)
+    }
+
+    #[test]
+    fn test_successors() -> Result<()> {
+        for width in [NodeWidth::U32, NodeWidth::U64] {
+            let graph = load(&small_graph(width))?;
+            assert_eq!(graph.width(), width);
+            assert_eq!(graph.num_nodes(), 3);
+            assert_eq!(graph.num_arcs(), 3);
+            assert_eq!(graph.outdegree(0), 2);
+            assert_eq!(graph.successors(0).collect::<Vec<_>>(), [1, 2]);
+            assert_eq!(graph.successors(1).len(), 0);
+            assert_eq!(graph.successors(2).collect::<Vec<_>>(), [0]);
+
+            let mut lists = Vec::new();
+            let mut iter = graph.iter();
+            while let Some((node, successors)) = iter.next() {
+                lists.push((node, successors.into_iter().collect::<Vec<_>>()));
+            }
+            assert_eq!(lists, [(0, vec![1, 2]), (1, vec![]), (2, vec![0])]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_graph() {
+        // decreasing offsets
+        assert!(load(&bin_file(NodeWidth::U32, 3, &[0, 2, 1, 3], &[1, 2, 0])).is_err());
+        // arc destination out of range
+        assert!(load(&bin_file(NodeWidth::U32, 3, &[0, 2, 2, 3], &[1, 3, 0])).is_err());
+        // too short to contain the fingerprint
+        assert!(load(&[0; 4]).is_err());
+    }
+
+    #[test]
+    fn test_fingerprint() -> Result<()> {
+        assert_eq!(NodeWidth::U32.fingerprint(), 0x84);
+        assert_eq!(NodeWidth::U64.fingerprint(), 0x88);
+        for width in [NodeWidth::U32, NodeWidth::U64] {
+            assert_eq!(NodeWidth::from_fingerprint(width.fingerprint())?, width);
+        }
+        assert!(NodeWidth::from_fingerprint(0).is_err());
+        assert!(NodeWidth::from_fingerprint(0x48).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_node() -> Result<()> {
+        for (width, node) in [
+            (NodeWidth::U32, 0),
+            (NodeWidth::U32, u32::MAX as usize),
+            (NodeWidth::U64, u32::MAX as usize + 1),
+            (NodeWidth::U64, usize::MAX),
+        ] {
+            let mut bytes = Vec::new();
+            width.write_node(&mut bytes, node)?;
+            assert_eq!(bytes.len(), width.bytes());
+            assert_eq!(width.read_node(&bytes), node);
+        }
+
+        let mut bytes = Vec::new();
+        assert!(NodeWidth::U32
+            .write_node(&mut bytes, u32::MAX as usize + 1)
+            .is_err());
+        assert!(bytes.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve() -> Result<()> {
+        let large = u32::MAX as usize + 1;
+        assert_eq!(NodeWidth::resolve(None, 10)?, NodeWidth::U32);
+        assert_eq!(NodeWidth::resolve(None, u32::MAX as usize)?, NodeWidth::U32);
+        assert_eq!(NodeWidth::resolve(None, large)?, NodeWidth::U64);
+        assert_eq!(
+            NodeWidth::resolve(Some(NodeWidth::U64), 10)?,
+            NodeWidth::U64
+        );
+        assert!(NodeWidth::resolve(Some(NodeWidth::U32), large).is_err());
+        Ok(())
+    }
+}
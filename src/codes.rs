@@ -0,0 +1,34 @@
+use clap::ValueEnum;
+use dsi_bitstream::prelude::Codes;
+
+/// The instantaneous codes that can be used to compress a component of a BvGraph.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Code {
+    Unary,
+    Gamma,
+    Delta,
+    Zeta1,
+    Zeta2,
+    Zeta3,
+    Zeta4,
+    Zeta5,
+    Zeta6,
+    Zeta7,
+}
+
+impl From<Code> for Codes {
+    fn from(code: Code) -> Self {
+        match code {
+            Code::Unary => Codes::Unary,
+            Code::Gamma => Codes::Gamma,
+            Code::Delta => Codes::Delta,
+            Code::Zeta1 => Codes::Zeta { k: 1 },
+            Code::Zeta2 => Codes::Zeta { k: 2 },
+            Code::Zeta3 => Codes::Zeta { k: 3 },
+            Code::Zeta4 => Codes::Zeta { k: 4 },
+            Code::Zeta5 => Codes::Zeta { k: 5 },
+            Code::Zeta6 => Codes::Zeta { k: 6 },
+            Code::Zeta7 => Codes::Zeta { k: 7 },
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use anyhow::{Context, Result};
+
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use dsi_bitstream::prelude::*;
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+use webgraph::prelude::*;
+
+use crate::{
+    bin_graph::{read_u64, BinHeader},
+    codes::Code,
+    utils::create_parent_dir,
+};
+
+pub const COMMAND_NAME: &str = "bin";
+
+#[derive(Args, Debug)]
+#[command(about = "Compresses a graph in the binary \"bin\" format used by Zuckerli to a BvGraph.", long_about = None)]
+pub struct CliArgs {
+    /// The path of the source graph in the bin format.
+    pub src: PathBuf,
+    /// The basename of the compressed graph.
+    pub dst: PathBuf,
+
+    #[clap(flatten)]
+    pub ca: CompressArgs,
+}
+
+/// The parameters used to compress the graph, the defaults are the same used by webgraph.
+#[derive(Args, Debug)]
+pub struct CompressArgs {
+    /// The size of the window used to search for references.
+    #[arg(short = 'w', long, default_value_t = 7)]
+    pub compression_window: usize,
+    /// The minimum length of an interval to be encoded as such.
+    #[arg(short = 'i', long, default_value_t = 4)]
+    pub min_interval_length: usize,
+    /// The maximum length of a chain of references.
+    #[arg(short = 'r', long, default_value_t = 3)]
+    pub max_ref_count: usize,
+    /// The code used for the outdegrees.
+    #[arg(value_enum, long, default_value = "gamma")]
+    pub outdegrees: Code,
+    /// The code used for the reference offsets.
+    #[arg(value_enum, long, default_value = "unary")]
+    pub references: Code,
+    /// The code used for the block counts and the blocks.
+    #[arg(value_enum, long, default_value = "gamma")]
+    pub blocks: Code,
+    /// The code used for the interval counts, the interval starts and the interval lengths.
+    #[arg(value_enum, long, default_value = "gamma")]
+    pub intervals: Code,
+    /// The code used for the first residual and the residuals.
+    #[arg(value_enum, long, default_value = "zeta3")]
+    pub residuals: Code,
+}
+
+impl From<&CompressArgs> for CompFlags {
+    fn from(args: &CompressArgs) -> Self {
+        CompFlags {
+            outdegrees: args.outdegrees.into(),
+            references: args.references.into(),
+            blocks: args.blocks.into(),
+            intervals: args.intervals.into(),
+            residuals: args.residuals.into(),
+            min_interval_length: args.min_interval_length,
+            compression_window: args.compression_window,
+            max_ref_count: args.max_ref_count,
+        }
+    }
+}
+
+/// Sequential reader for graphs in the bin format: it streams the offsets and the arcs
+/// through two independent buffered readers over the same file, so only the successors
+/// of the current node are kept in memory.
+///
+/// The header and the size of the file are checked when the reader is opened, while
+/// the offsets and the arcs are checked while they are read: as the reader is consumed
+/// by the compressor, which can not handle errors, the iteration stops at the first
+/// invalid list, and the error is stored in [`error`](Self::error).
+struct BinGraphReader {
+    header: BinHeader,
+    offsets: BufReader<File>,
+    arcs: BufReader<File>,
+    next_node: usize,
+    next_offset: u64,
+    /// The error that stopped the iteration, if any.
+    error: Option<anyhow::Error>,
+}
+
+impl BinGraphReader {
+    fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let open = || {
+            File::open(path)
+                .with_context(|| format!("Failed to open file {:?}", path.to_string_lossy()))
+        };
+        let mut offsets = BufReader::new(open()?);
+        let file_len = std::fs::metadata(path)?.len();
+        let header = BinHeader::read(&mut offsets, file_len)
+            .with_context(|| format!("Invalid bin graph {:?}", path.to_string_lossy()))?;
+
+        // the first offset, which is zero, has already been checked
+        offsets.seek(SeekFrom::Start(header.offsets_start() + 8))?;
+        let mut arcs = BufReader::new(open()?);
+        arcs.seek(SeekFrom::Start(header.arcs_start()))?;
+
+        Ok(BinGraphReader {
+            header,
+            offsets,
+            arcs,
+            next_node: 0,
+            next_offset: 0,
+            error: None,
+        })
+    }
+
+    fn num_nodes(&self) -> usize {
+        self.header.num_nodes
+    }
+
+    fn num_arcs(&self) -> u64 {
+        self.header.num_arcs
+    }
+
+    /// Reads the successors of the next node.
+    fn read_successors(&mut self) -> Result<Vec<usize>> {
+        let node = self.next_node;
+        let offset = self.next_offset;
+        self.next_offset = read_u64(&mut self.offsets).context("Failed to read offset")?;
+        anyhow::ensure!(
+            offset <= self.next_offset && self.next_offset <= self.header.num_arcs,
+            "Invalid offsets for node {}: {}..{} (total number of arcs {})",
+            node,
+            offset,
+            self.next_offset,
+            self.header.num_arcs
+        );
+
+        let width = self.header.width;
+        (offset..self.next_offset)
+            .map(|_| {
+                let mut arc_bytes = [0u8; 8];
+                let arc_bytes = &mut arc_bytes[..width.bytes()];
+                self.arcs
+                    .read_exact(arc_bytes)
+                    .context("Failed to read arc destination")?;
+                let arc = width.read_node(arc_bytes);
+                anyhow::ensure!(
+                    arc < self.header.num_nodes,
+                    "Invalid arc destination: {} >= number of nodes ({})",
+                    arc,
+                    self.header.num_nodes
+                );
+                Ok(arc)
+            })
+            .collect()
+    }
+}
+
+impl Iterator for BinGraphReader {
+    type Item = (usize, Vec<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_node == self.header.num_nodes || self.error.is_some() {
+            return None;
+        }
+        match self.read_successors() {
+            Ok(successors) => {
+                let node = self.next_node;
+                self.next_node += 1;
+                Some((node, successors))
+            }
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let start = std::time::Instant::now();
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    create_parent_dir(&args.dst)?;
+
+    let mut graph = BinGraphReader::new(&args.src)?;
+    let num_nodes = graph.num_nodes();
+    log::info!(
+        "Compressing a graph with {} nodes and {} arcs",
+        num_nodes,
+        graph.num_arcs()
+    );
+
+    let result = BvComp::single_thread::<BE, _>(
+        &args.dst,
+        lender::from_iter(&mut graph),
+        CompFlags::from(&args.ca),
+        true, // build the offsets
+        Some(num_nodes),
+    );
+    // an invalid bin graph stops the compression early
+    if let Some(error) = graph.error.take() {
+        return Err(error)
+            .with_context(|| format!("Invalid bin graph {:?}", args.src.to_string_lossy()));
+    }
+    result?;
+
+    log::info!(
+        "The compression took {:.3} seconds",
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
@@ -2,8 +2,14 @@ use anyhow::Result;
 use clap::Command;
 
 pub mod analyze;
+pub mod bin_graph;
+pub mod codes;
+pub mod from;
+pub mod npy;
 pub mod run;
 pub mod to;
+pub mod utils;
+pub mod verify;
 
 pub fn main() -> Result<()> {
     let args = std::env::args_os();
@@ -19,8 +25,10 @@ TMPDIR: where to store temporary files (potentially very large ones)
         );
 
     let command = to::cli(command);
+    let command = from::cli(command);
     let command = analyze::cli(command);
     let command = run::cli(command);
+    let command = verify::cli(command);
     let command = command.display_order(0); // sort args alphabetically
     let mut completion_command = command.clone();
     let matches = command.get_matches_from(args);
@@ -32,8 +40,10 @@ TMPDIR: where to store temporary files (potentially very large ones)
     }
     match subcommand.unwrap() {
         (to::COMMAND_NAME, sub_m) => to::main(sub_m),
+        (from::COMMAND_NAME, sub_m) => from::main(sub_m),
         (run::COMMAND_NAME, sub_m) => run::main(sub_m),
         (analyze::COMMAND_NAME, sub_m) => analyze::main(sub_m),
+        (verify::COMMAND_NAME, sub_m) => verify::main(sub_m),
         (command_name, _) => {
             // this shouldn't happen as clap should catch this
             eprintln!("Unknown command: {:?}", command_name);
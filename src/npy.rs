@@ -0,0 +1,253 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::{
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// The length of the header, including magic string, version and padding: it is
+/// a multiple of 64 as required by the format, and large enough for any shape
+/// of a one- or two-dimensional array, so the header can be rewritten in place once the
+/// length of the array is known.
+const HEADER_LEN: usize = 128;
+
+/// The data types that can be written in a `.npy` file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Dtype {
+    Int32,
+    Int64,
+    #[value(skip)]
+    Uint32,
+    #[value(skip)]
+    Uint64,
+}
+
+impl Dtype {
+    /// The little-endian type descriptor used in the header.
+    fn descr(self) -> &'static str {
+        match self {
+            Dtype::Int32 => "<i4",
+            Dtype::Int64 => "<i8",
+            Dtype::Uint32 => "<u4",
+            Dtype::Uint64 => "<u8",
+        }
+    }
+
+    /// The largest value that can be represented by the type.
+    pub fn max(self) -> u64 {
+        match self {
+            Dtype::Int32 => i32::MAX as u64,
+            Dtype::Int64 => i64::MAX as u64,
+            Dtype::Uint32 => u32::MAX as u64,
+            Dtype::Uint64 => u64::MAX,
+        }
+    }
+}
+
+/// Writer of one-dimensional NumPy arrays (version 1.0 of the `.npy` format)
+/// of unknown length: the values are streamed after a placeholder header,
+/// which is completed with the actual shape by [`finish`](NpyWriter::finish).
+///
+/// Two-dimensional arrays with a given number of columns and an unknown number
+/// of rows can be written with [`with_columns`](NpyWriter::with_columns),
+/// pushing the values row by row.
+pub struct NpyWriter<W: Write + Seek> {
+    writer: W,
+    dtype: Dtype,
+    len: u64,
+    /// The number of columns of a two-dimensional array, or `None`.
+    columns: Option<u64>,
+}
+
+impl NpyWriter<BufWriter<File>> {
+    /// Creates a `.npy` file at `path`.
+    pub fn create(path: impl AsRef<Path>, dtype: Dtype) -> Result<Self> {
+        let file = File::create(path.as_ref()).with_context(|| {
+            format!(
+                "Failed to create the file {:?}",
+                path.as_ref().to_string_lossy()
+            )
+        })?;
+        Self::new(BufWriter::new(file), dtype)
+    }
+}
+
+impl<W: Write + Seek> NpyWriter<W> {
+    pub fn new(mut writer: W, dtype: Dtype) -> Result<Self> {
+        write_header(&mut writer, dtype, 0, None)?;
+        Ok(Self {
+            writer,
+            dtype,
+            len: 0,
+            columns: None,
+        })
+    }
+
+    /// Makes the array two-dimensional, with `columns` columns.
+    pub fn with_columns(mut self, columns: usize) -> Result<Self> {
+        anyhow::ensure!(self.len == 0, "The shape must be set before pushing values");
+        anyhow::ensure!(columns > 0, "The number of columns must be positive");
+        self.columns = Some(columns as u64);
+        self.writer.seek(SeekFrom::Start(0))?;
+        write_header(&mut self.writer, self.dtype, 0, self.columns)?;
+        Ok(self)
+    }
+
+    /// Appends `value` to the array, failing if it does not fit in the data type.
+    #[inline(always)]
+    pub fn push(&mut self, value: u64) -> Result<()> {
+        anyhow::ensure!(
+            value <= self.dtype.max(),
+            "Value {} does not fit in {:?}",
+            value,
+            self.dtype
+        );
+        match self.dtype {
+            Dtype::Int32 => self.writer.write_all(&(value as i32).to_le_bytes())?,
+            Dtype::Int64 => self.writer.write_all(&(value as i64).to_le_bytes())?,
+            Dtype::Uint32 => self.writer.write_all(&(value as u32).to_le_bytes())?,
+            Dtype::Uint64 => self.writer.write_all(&value.to_le_bytes())?,
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Writes the final shape in the header and returns the inner writer.
+    pub fn finish(mut self) -> Result<W> {
+        let rows = match self.columns {
+            Some(columns) => {
+                anyhow::ensure!(
+                    self.len % columns == 0,
+                    "The last row of the array is incomplete"
+                );
+                self.len / columns
+            }
+            None => self.len,
+        };
+        self.writer.seek(SeekFrom::Start(0))?;
+        write_header(&mut self.writer, self.dtype, rows, self.columns)?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush().context("Failed to flush .npy file")?;
+        Ok(self.writer)
+    }
+}
+
+/// Writes the header of an array of `len` elements, or of `len` rows if the
+/// array is two-dimensional, padded to [`HEADER_LEN`] bytes.
+fn write_header(
+    writer: &mut impl Write,
+    dtype: Dtype,
+    len: u64,
+    columns: Option<u64>,
+) -> Result<()> {
+    let shape = match columns {
+        Some(columns) => format!("({}, {})", len, columns),
+        None => format!("({},)", len),
+    };
+    let mut dict = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        dtype.descr(),
+        shape
+    );
+    // magic string (6 bytes), version (2 bytes) and header length (2 bytes)
+    let padding = HEADER_LEN - 10 - dict.len() - 1;
+    dict.push_str(&" ".repeat(padding));
+    dict.push('\n');
+
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(dict.len() as u16).to_le_bytes())?;
+    writer
+        .write_all(dict.as_bytes())
+        .context("Failed to write .npy header")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Writes `values` and returns the header dictionary and the data.
+    fn write(dtype: Dtype, columns: Option<usize>, values: &[u64]) -> Result<(String, Vec<u8>)> {
+        let mut writer = NpyWriter::new(Cursor::new(Vec::new()), dtype)?;
+        if let Some(columns) = columns {
+            writer = writer.with_columns(columns)?;
+        }
+        for &value in values {
+            writer.push(value)?;
+        }
+        let bytes = writer.finish()?.into_inner();
+
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        assert_eq!(
+            u16::from_le_bytes([bytes[8], bytes[9]]) as usize,
+            HEADER_LEN - 10
+        );
+        assert_eq!(bytes[HEADER_LEN - 1], b'\n');
+        let dict = std::str::from_utf8(&bytes[10..HEADER_LEN])?
+            .trim_end()
+            .to_string();
+        Ok((dict, bytes[HEADER_LEN..].to_vec()))
+    }
+
+    #[test]
+    fn test_one_dimensional() -> Result<()> {
+        let (dict, data) = write(Dtype::Int32, None, &[1, 2, 3])?;
+        assert_eq!(
+            dict,
+            "{'descr': '<i4', 'fortran_order': False, 'shape': (3,), }"
+        );
+        assert_eq!(data, [1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]);
+
+        let (dict, data) = write(Dtype::Int64, None, &[])?;
+        assert_eq!(
+            dict,
+            "{'descr': '<i8', 'fortran_order': False, 'shape': (0,), }"
+        );
+        assert!(data.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_two_dimensional() -> Result<()> {
+        let (dict, data) = write(Dtype::Uint64, Some(2), &[1, 2, 3, 4])?;
+        assert_eq!(
+            dict,
+            "{'descr': '<u8', 'fortran_order': False, 'shape': (2, 2), }"
+        );
+        assert_eq!(data.len(), 4 * 8);
+        assert_eq!(data[16..24], 3_u64.to_le_bytes());
+
+        // the last row must be complete
+        let mut writer = NpyWriter::new(Cursor::new(Vec::new()), Dtype::Uint32)?.with_columns(2)?;
+        writer.push(1)?;
+        assert!(writer.finish().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_largest_header() -> Result<()> {
+        let mut header = Vec::new();
+        write_header(&mut header, Dtype::Uint64, u64::MAX, Some(u64::MAX))?;
+        assert_eq!(header.len(), HEADER_LEN);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dtype_bounds() -> Result<()> {
+        let mut writer = NpyWriter::new(Cursor::new(Vec::new()), Dtype::Int32)?;
+        writer.push(i32::MAX as u64)?;
+        assert!(writer.push(i32::MAX as u64 + 1).is_err());
+
+        let mut writer = NpyWriter::new(Cursor::new(Vec::new()), Dtype::Uint32)?;
+        writer.push(u32::MAX as u64)?;
+        assert!(writer.push(u32::MAX as u64 + 1).is_err());
+
+        let mut writer = NpyWriter::new(Cursor::new(Vec::new()), Dtype::Int64)?;
+        assert!(writer.push(u64::MAX).is_err());
+        let mut writer = NpyWriter::new(Cursor::new(Vec::new()), Dtype::Uint64)?;
+        writer.push(u64::MAX)?;
+        Ok(())
+    }
+}
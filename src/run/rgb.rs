@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 
 use clap::{ArgMatches, Args, Command, FromArgMatches};
-use dsi_bitstream::prelude::*;
+use dsi_bitstream::{dispatch::factory::CodesReaderFactoryHelper, prelude::*};
 use dsi_progress_logger::prelude::*;
 use lender::Lender;
 use std::{
@@ -11,6 +11,12 @@ use std::{
 };
 use webgraph::prelude::*;
 
+use crate::{
+    bin_graph::{is_bin_graph, BinGraphMmap},
+    dispatch_endianness,
+    utils::create_parent_dir,
+};
+
 pub const COMMAND_NAME: &str = "rgb";
 
 #[derive(Args, Debug)]
@@ -19,7 +25,7 @@ pub const COMMAND_NAME: &str = "rgb";
     long_about = "Reorder the graph using the Recursive Graph Bisection algorithm, based on the implementation https://github.com/JMMackenzie/enhanced-graph-bisection from the paper \"Faster Index Reordering with Bipartite Graph Partitioning by Joel Mackenzie, Matthias Petri, and Alistair Moffat\"."
 )]
 pub struct CliArgs {
-    /// The basename of the source graph.
+    /// The basename of the source graph, or the path of a graph in the bin format.
     pub src: PathBuf,
     /// The output path of the permutation calculated by recursive graph bisection.
     pub dst: PathBuf,
@@ -56,22 +62,6 @@ pub struct CliArgs {
     save_clusters: bool,
 }
 
-// TODO: this functions are duplicated from webgraph but they are not exposed.
-//       duplicate them to keep a semantic as close as possible to webgraph graphs
-/// Creates all parent directories of the given file path.
-fn create_parent_dir(file_path: impl AsRef<Path>) -> Result<()> {
-    // ensure that the dst directory exists
-    if let Some(parent_dir) = file_path.as_ref().parent() {
-        std::fs::create_dir_all(parent_dir).with_context(|| {
-            format!(
-                "Failed to create the directory {:?}",
-                parent_dir.to_string_lossy()
-            )
-        })?;
-    }
-    Ok(())
-}
-
 fn store_perm(data: &[usize], perm: impl AsRef<Path>) -> Result<()> {
     let mut file = std::fs::File::create(&perm).with_context(|| {
         format!(
@@ -173,14 +163,31 @@ pub fn store_clustr_file(
 }
 
 pub fn main(submatches: &ArgMatches) -> Result<()> {
-    let start = std::time::Instant::now();
     let args = CliArgs::from_arg_matches(submatches)?;
 
     create_parent_dir(&args.dst)?;
 
+    if is_bin_graph(&args.src) {
+        let graph = BinGraphMmap::load(&args.src)?;
+        reorder_graph(&graph, args)
+    } else {
+        dispatch_endianness!(&args.src, reorder_bvgraph(args))
+    }
+}
+
+pub fn reorder_bvgraph<E: Endianness + 'static>(args: CliArgs) -> Result<()>
+where
+    MmapHelper<u32>: CodesReaderFactoryHelper<E>,
+    for<'a> LoadModeCodesReader<'a, E, Mmap>: BitSeek,
+{
     let graph = BvGraphSeq::with_basename(&args.src)
-        .endianness::<BE>()
+        .endianness::<E>()
         .load()?;
+    reorder_graph(&graph, args)
+}
+
+pub fn reorder_graph<G: SequentialGraph>(graph: &G, args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
 
     let mut pl = progress_logger!(
         display_memory = true,
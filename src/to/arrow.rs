@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+
+use ::arrow::{
+    array::{ArrayRef, UInt32Array, UInt64Array},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    ipc::writer::FileWriter,
+    record_batch::RecordBatch,
+};
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use dsi_progress_logger::prelude::*;
+use lender::Lender;
+use std::{fs::File, io::BufWriter, path::PathBuf, sync::Arc, time::Duration};
+use webgraph::prelude::*;
+
+use crate::{bin_graph::NodeWidth, dispatch_graph, utils::create_parent_dir};
+
+pub const COMMAND_NAME: &str = "arrow";
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Decompresses a BvGraph to an Arrow IPC file with a (src, dst) row for each arc.",
+    long_about = "Decompresses a BvGraph to an Arrow IPC file containing a table with two columns, src and dst, and a row for each arc, which can be queried directly by DuckDB, Polars or pyarrow."
+)]
+pub struct CliArgs {
+    /// The basename of the source graph, or the path of a graph in the bin format.
+    pub src: PathBuf,
+    /// The output path of the Arrow IPC file.
+    pub dst: PathBuf,
+
+    /// The type of the columns: by default u32, unless the graph has more than 2^32 - 1 nodes.
+    #[arg(short, long, value_enum)]
+    pub width: Option<NodeWidth>,
+
+    /// The number of rows of each record batch.
+    #[arg(short, long, default_value = "1048576")]
+    pub batch_size: usize,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    dispatch_graph!(&args.src, to_arrow(args))
+}
+
+pub fn to_arrow(graph: &impl SequentialGraph, args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    create_parent_dir(&args.dst)?;
+
+    let width = NodeWidth::resolve(args.width, graph.num_nodes())?;
+    let schema = edge_schema(width);
+
+    let file = File::create(&args.dst)
+        .with_context(|| format!("Failed to create the file {:?}", args.dst.to_string_lossy()))?;
+    let mut writer = FileWriter::try_new(BufWriter::new(file), &schema)
+        .context("Failed to create the Arrow IPC writer")?;
+
+    let mut pl = ProgressLogger::default();
+    pl.log_interval(Duration::from_secs(60));
+    pl.item_name("node")
+        .expected_updates(Some(graph.num_nodes()));
+    pl.start("Writing the arcs...");
+    for_each_batch(graph, &schema, width, args.batch_size, &mut pl, |batch| {
+        writer.write(&batch).context("Failed to write record batch")
+    })?;
+    pl.done();
+    writer
+        .finish()
+        .context("Failed to finish the Arrow IPC file")?;
+
+    log::info!(
+        "The conversion took {:.3} seconds",
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+/// Returns the schema of the table of the arcs, with a non-nullable src and dst
+/// column of unsigned integers of the given width.
+pub(crate) fn edge_schema(width: NodeWidth) -> SchemaRef {
+    let data_type = match width {
+        NodeWidth::U32 => DataType::UInt32,
+        NodeWidth::U64 => DataType::UInt64,
+    };
+    Arc::new(Schema::new(vec![
+        Field::new("src", data_type.clone(), false),
+        Field::new("dst", data_type, false),
+    ]))
+}
+
+fn column(values: &[u64], width: NodeWidth) -> ArrayRef {
+    match width {
+        NodeWidth::U32 => Arc::new(UInt32Array::from_iter_values(
+            values.iter().map(|&value| value as u32),
+        )),
+        NodeWidth::U64 => Arc::new(UInt64Array::from(values.to_vec())),
+    }
+}
+
+/// Streams the arcs of `graph` in record batches of (at most) `batch_size`
+/// rows, passing each batch to `write`.
+pub(crate) fn for_each_batch(
+    graph: &impl SequentialGraph,
+    schema: &SchemaRef,
+    width: NodeWidth,
+    batch_size: usize,
+    pl: &mut impl ProgressLog,
+    mut write: impl FnMut(RecordBatch) -> Result<()>,
+) -> Result<()> {
+    anyhow::ensure!(batch_size > 0, "The batch size must be positive");
+    let mut srcs = Vec::with_capacity(batch_size);
+    let mut dsts = Vec::with_capacity(batch_size);
+    let mut flush = |srcs: &mut Vec<u64>, dsts: &mut Vec<u64>| -> Result<()> {
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![column(srcs, width), column(dsts, width)],
+        )
+        .context("Failed to build record batch")?;
+        srcs.clear();
+        dsts.clear();
+        write(batch)
+    };
+
+    let mut iter = graph.iter();
+    while let Some((node, successors)) = iter.next() {
+        for successor in successors {
+            srcs.push(node as u64);
+            dsts.push(successor as u64);
+            if srcs.len() == batch_size {
+                flush(&mut srcs, &mut dsts)?;
+            }
+        }
+        pl.light_update();
+    }
+    if !srcs.is_empty() {
+        flush(&mut srcs, &mut dsts)?;
+    }
+    Ok(())
+}
@@ -1,161 +1,77 @@
 use anyhow::{Context, Result};
 
 use clap::{ArgMatches, Args, Command, FromArgMatches};
-use dsi_bitstream::prelude::*;
+use dsi_bitstream::{dispatch::factory::CodesReaderFactoryHelper, prelude::*};
 use dsi_progress_logger::prelude::*;
 use lender::Lender;
+use rayon::prelude::*;
 use std::{
-    fs::File,
-    io::{copy, BufReader, BufWriter, Read, Write},
+    fs::{File, OpenOptions},
+    io::{copy, BufReader, BufWriter, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
+    sync::Mutex,
     time::Duration,
 };
-use webgraph::prelude::*;
+use webgraph::{prelude::*, transform::transpose, utils::MemoryUsage};
+
+use crate::{
+    bin_graph::{is_bin_graph, BinGraphMmap, NodeWidth},
+    dispatch_endianness, dispatch_graph,
+    to::selection::SelectionArgs,
+    utils::{append_extension, create_parent_dir, symmetrize, CHUNKS_PER_THREAD},
+};
 
 pub const COMMAND_NAME: &str = "bin";
 
 #[derive(Args, Debug)]
 #[command(about = "Decompresses a BvGraph, to the binary \"bin\" format used by Zuckerli to compress graphs.", long_about = None)]
 pub struct CliArgs {
-    /// The basename of the source graph.
+    /// The basename of the source graph, or the path of a graph in the bin format.
     pub src: PathBuf,
     /// The output path of the decompressed graph.
     pub dst: PathBuf,
-}
-
-// Simple on-disk representation of a graph that can directly mapped into memory
-// (allowing reduced memory usage).
-// Format description:
-// - 8 bytes of fingerprint
-// - 4 bytes to represent the number of nodes N
-// - N+1 8-byte integers that represent the index of the first edge of the i-th
-//   adjacency list. The last of these integers is the total number of edges, M.
-// - M 4-byte integers that represent the destination node of each graph edge.
-struct BinGraph {
-    offsets: Vec<u64>,
-    arcs: Vec<u32>,
-}
-
-impl BinGraph {
-    fn add_list(&mut self, node_index: usize, successors: impl IntoIterator<Item = usize>) {
-        assert_eq!(node_index, self.offsets.len());
-        let total_arcs = self.arcs.len();
-        self.offsets.push(total_arcs as u64);
-        self.arcs.extend(successors.into_iter().map(|x| x as u32));
-    }
-
-    fn new() -> Self {
-        BinGraph {
-            offsets: Vec::new(),
-            arcs: Vec::new(),
-        }
-    }
-
-    fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
-        let file = File::open(path.as_ref()).with_context(|| {
-            format!("Failed to open file {:?}", path.as_ref().to_string_lossy())
-        })?;
-        let mut reader = BufReader::new(file);
-
-        // Read and verify fingerprint (8 bytes)
-        let mut fingerprint_bytes = [0u8; 8];
-        reader
-            .read_exact(&mut fingerprint_bytes)
-            .context("Failed to read fingerprint from file")?;
-        let fingerprint = u64::from_le_bytes(fingerprint_bytes);
-
-        // Expected fingerprint: (size_of::<u64>() << 4) | size_of::<u32>()
-        let expected_fingerprint =
-            (std::mem::size_of::<u64>() as u64) << 4 | std::mem::size_of::<u32>() as u64;
-        anyhow::ensure!(
-            fingerprint == expected_fingerprint,
-            "Invalid fingerprint: expected {}, got {}",
-            expected_fingerprint,
-            fingerprint
-        );
-
-        // Read number of nodes (4 bytes)
-        let mut num_nodes_bytes = [0u8; 4];
-        reader
-            .read_exact(&mut num_nodes_bytes)
-            .context("Failed to read number of nodes")?;
-        let num_nodes = u32::from_le_bytes(num_nodes_bytes) as usize;
-
-        // Read offsets (N+1 8-byte integers)
-        let mut offsets = Vec::with_capacity(num_nodes);
-        let mut total_arcs = 0u64;
-        for i in 0..=num_nodes {
-            let mut offset_bytes = [0u8; 8];
-            reader
-                .read_exact(&mut offset_bytes)
-                .context("Failed to read offset")?;
-            let offset = u64::from_le_bytes(offset_bytes);
-
-            if i == num_nodes {
-                // The last offset is the total number of arcs
-                total_arcs = offset;
-            } else {
-                offsets.push(offset);
-            }
-        }
-
-        // Read arcs (M 4-byte integers) and verify each arc is < num_nodes
-        let mut arcs = Vec::with_capacity(total_arcs as usize);
-        for _ in 0..total_arcs {
-            let mut arc_bytes = [0u8; 4];
-            reader
-                .read_exact(&mut arc_bytes)
-                .context("Failed to read arc destination")?;
-            let arc = u32::from_le_bytes(arc_bytes) as usize;
-
-            anyhow::ensure!(
-                arc < num_nodes,
-                "Invalid arc destination: {} >= number of nodes ({})",
-                arc,
-                num_nodes
-            );
-            arcs.push(arc as u32);
-        }
-
-        Ok(BinGraph { offsets, arcs })
-    }
 
-    fn write(&self, path: PathBuf) -> anyhow::Result<()> {
-        // create the file if not exists
-        let file = File::create(&path)?;
-        let mut writer = BufWriter::new(file);
-        // Fingerprint of the simple uncompressed graph format: number of bytes to
-        // represent the number of edges followed by number of bytes to represent the
-        // number of nodes.
-        let fingerprint =
-            (std::mem::size_of::<u64>() as u64) << 4 | std::mem::size_of::<u32>() as u64;
-        // write the fingerprint
-        writer.write_all(&fingerprint.to_le_bytes())?;
-        // write the total number of nodes
-        let number_of_nodes = self.offsets.len() as u32;
-        writer.write_all(&number_of_nodes.to_le_bytes())?;
-        // adds to the offsets the total number of arcs
-        // write the offsets
-        for offset in self.offsets.iter() {
-            writer.write_all(&offset.to_le_bytes())?;
-        }
-        let total_arcs = self.arcs.len() as u64;
-        writer.write_all(&total_arcs.to_le_bytes())?;
-        // write the nodes
-        for node in self.arcs.iter() {
-            writer.write_all(&node.to_le_bytes())?;
-        }
-        writer.flush()?;
-        Ok(())
-    }
+    /// Decode the graph in parallel using its random-access version (for a BvGraph,
+    /// it requires the Elias–Fano offsets, i.e., the .ef file), writing each chunk of
+    /// nodes directly at its final position in the output file.
+    #[arg(short, long, conflicts_with = "nodes")]
+    pub parallel: bool,
+
+    /// The number of threads used in parallel mode (defaults to the number of available cores).
+    #[arg(short, long, requires = "parallel")]
+    pub threads: Option<usize>,
+
+    /// The width of the node identifiers: by default u32, as in Zuckerli, unless
+    /// the graph has more than 2^32 - 1 nodes.
+    #[arg(short, long, value_enum)]
+    pub width: Option<NodeWidth>,
+
+    /// Write the transposed graph, computed with external-memory sorting
+    /// (the batches are stored in TMPDIR).
+    #[arg(long, conflicts_with_all = ["symmetrize", "parallel"])]
+    pub transpose: bool,
+
+    /// Write the symmetrized graph, without self-loops and duplicate arcs, computed
+    /// with external-memory sorting (the batches are stored in TMPDIR).
+    #[arg(long, conflicts_with = "parallel")]
+    pub symmetrize: bool,
+
+    /// The number of arcs sorted in memory in each batch when transposing or
+    /// symmetrizing the graph.
+    #[arg(short, long, default_value = "10000000")]
+    pub batch_size: usize,
+
+    #[clap(flatten)]
+    pub selection: SelectionArgs,
 }
 
 /// Writer for large bin (uncompressed) graphs: it allows the adjacency list of the next node
-/// to be added incrementally, but, unlike the implementation in `BinGraph`, it writes both the
-/// offsets and the arcs to two separate files created in the `temp_path` during the instantiation of the object.
+/// to be added incrementally, writing both the offsets and the arcs to two separate files
+/// created in the `temp_path` during the instantiation of the object.
 /// Finally, it is possible to write the entire graph to a single file that conforms to the
 /// bin graph file specification.
-struct BinGraphWriter {
+pub(crate) struct BinGraphWriter {
+    width: NodeWidth,
     offset_file: PathBuf,
     arc_file: PathBuf,
     offset_writer: BufWriter<File>,
@@ -164,27 +80,59 @@ struct BinGraphWriter {
     arc_count: u64,
 }
 
+/// The files written by a [`BinGraphWriter`]: the offsets are 8-byte little-endian
+/// integers, one for each node (without the final total number of arcs), and the arcs
+/// are little-endian integers of the width of the writer.
+pub(crate) struct BinGraphParts {
+    pub offset_file: PathBuf,
+    pub arc_file: PathBuf,
+    pub node_count: u64,
+    pub arc_count: u64,
+}
+
 impl BinGraphWriter {
-    fn new(temp_path: &Path) -> Self {
-        let offset_file = temp_path.join("offsets.tmp");
-        let arc_file = temp_path.join("arcs.tmp");
+    fn new(temp_path: &Path, width: NodeWidth) -> Result<Self> {
+        Self::with_files(
+            temp_path.join("offsets.tmp"),
+            temp_path.join("arcs.tmp"),
+            width,
+        )
+    }
 
-        let offset_writer =
-            BufWriter::new(File::create(&offset_file).expect("Failed to create offset file"));
-        let arc_writer =
-            BufWriter::new(File::create(&arc_file).expect("Failed to create arc file"));
+    /// Creates a writer of the offsets and of the arcs to the given files.
+    pub(crate) fn with_files(
+        offset_file: PathBuf,
+        arc_file: PathBuf,
+        width: NodeWidth,
+    ) -> Result<Self> {
+        let offset_writer = BufWriter::new(File::create(&offset_file).with_context(|| {
+            format!(
+                "Failed to create the offset file {:?}",
+                offset_file.to_string_lossy()
+            )
+        })?);
+        let arc_writer = BufWriter::new(File::create(&arc_file).with_context(|| {
+            format!(
+                "Failed to create the arc file {:?}",
+                arc_file.to_string_lossy()
+            )
+        })?);
 
-        BinGraphWriter {
+        Ok(BinGraphWriter {
+            width,
             offset_file,
             arc_file,
             offset_writer,
             arc_writer,
             arc_count: 0,
             node_count: 0,
-        }
+        })
     }
 
-    fn add_list(&mut self, successors: impl IntoIterator<Item = usize>) -> anyhow::Result<()> {
+    pub(crate) fn add_list(
+        &mut self,
+        successors: impl IntoIterator<Item = usize>,
+    ) -> anyhow::Result<()> {
         // Write current arc count as offset for this node
         self.offset_writer
             .write_all(&self.arc_count.to_le_bytes())
@@ -192,8 +140,8 @@ impl BinGraphWriter {
 
         // Write all successors and update arc count
         for successor in successors {
-            self.arc_writer
-                .write_all(&(successor as u32).to_le_bytes())
+            self.width
+                .write_node(&mut self.arc_writer, successor)
                 .context("Failed to write arc")?;
             self.arc_count += 1;
         }
@@ -202,86 +150,121 @@ impl BinGraphWriter {
         Ok(())
     }
 
-    fn write(mut self, path: PathBuf) -> anyhow::Result<()> {
-        create_parent_dir(&path)?;
-
-        // Flush and close temporary file writers
+    /// Flushes and closes the offsets and arcs files, returning them.
+    pub(crate) fn into_parts(mut self) -> anyhow::Result<BinGraphParts> {
         self.offset_writer
             .flush()
             .context("Failed to flush offset writer")?;
         self.arc_writer
             .flush()
             .context("Failed to flush arc writer")?;
-        drop(self.offset_writer);
-        drop(self.arc_writer);
+        Ok(BinGraphParts {
+            offset_file: self.offset_file,
+            arc_file: self.arc_file,
+            node_count: self.node_count,
+            arc_count: self.arc_count,
+        })
+    }
+
+    fn write(self, path: PathBuf) -> anyhow::Result<()> {
+        create_parent_dir(&path)?;
+
+        let width = self.width;
+        // Flush and close temporary file writers
+        let parts = self.into_parts()?;
 
         let mut output_file = BufWriter::new(File::create(&path)?);
 
         // Fingerprint of the simple uncompressed graph format
-        let fingerprint =
-            (std::mem::size_of::<u64>() as u64) << 4 | std::mem::size_of::<u32>() as u64;
         output_file
-            .write_all(&fingerprint.to_le_bytes())
+            .write_all(&width.fingerprint().to_le_bytes())
             .context("Failed to write fingerprint")?;
 
         // Write number of nodes
-        let num_nodes = self.node_count as u32;
-        output_file
-            .write_all(&num_nodes.to_le_bytes())
+        width
+            .write_node(&mut output_file, parts.node_count as usize)
             .context("Failed to write number of nodes")?;
 
         // Copy offsets file in buffered chunks
-        let offset_input = File::open(&self.offset_file).context("Failed to open offsets file")?;
+        let offset_input = File::open(&parts.offset_file).context("Failed to open offsets file")?;
         let mut offset_reader = BufReader::new(offset_input);
         copy(&mut offset_reader, &mut output_file).context("Failed to copy offsets")?;
 
         // Write total arc count
         output_file
-            .write_all(&self.arc_count.to_le_bytes())
+            .write_all(&parts.arc_count.to_le_bytes())
             .context("Failed to write total arc count")?;
 
         // Copy arcs file in buffered chunks
-        let arc_input = File::open(&self.arc_file).context("Failed to open arcs file")?;
+        let arc_input = File::open(&parts.arc_file).context("Failed to open arcs file")?;
         let mut arc_reader = BufReader::new(arc_input);
         copy(&mut arc_reader, &mut output_file).context("Failed to copy arcs")?;
 
         output_file.flush().context("Failed to flush output file")?;
 
         // Clean up temp files
-        std::fs::remove_file(&self.offset_file).context("Failed to remove offset temp file")?;
-        std::fs::remove_file(&self.arc_file).context("Failed to remove arc temp file")?;
+        std::fs::remove_file(&parts.offset_file).context("Failed to remove offset temp file")?;
+        std::fs::remove_file(&parts.arc_file).context("Failed to remove arc temp file")?;
 
         Ok(())
     }
 }
 
-/// Creates all parent directories of the given file path.
-fn create_parent_dir(file_path: impl AsRef<Path>) -> Result<()> {
-    // ensure that the dst directory exists
-    if let Some(parent_dir) = file_path.as_ref().parent() {
-        std::fs::create_dir_all(parent_dir).with_context(|| {
-            format!(
-                "Failed to create the directory {:?}",
-                parent_dir.to_string_lossy()
-            )
-        })?;
-    }
-    Ok(())
-}
-
 pub fn cli(command: Command) -> Command {
     command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
 }
 
 pub fn main(submatches: &ArgMatches) -> Result<()> {
-    let start = std::time::Instant::now();
     let args = CliArgs::from_arg_matches(submatches)?;
 
+    if !args.parallel {
+        dispatch_graph!(&args.src, to_bin(args))
+    } else if is_bin_graph(&args.src) {
+        let graph = BinGraphMmap::load(&args.src)?;
+        to_bin_parallel(&graph, args)
+    } else {
+        dispatch_endianness!(&args.src, to_bin_parallel_bvgraph(args))
+    }
+}
+
+pub fn to_bin(graph: &impl SequentialGraph, args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
     create_parent_dir(&args.dst)?;
 
-    let graph = BvGraphSeq::with_basename(&args.src)
-        .endianness::<BE>()
-        .load()?;
+    let width = NodeWidth::resolve(args.width, graph.num_nodes())?;
+    let memory_usage = MemoryUsage::BatchSize(args.batch_size);
+
+    if args.transpose {
+        let transposed = transpose(graph, memory_usage).context("Failed to transpose the graph")?;
+        write_graph(&transposed, width, &args, "Writing the transposed graph...")?;
+    } else if args.symmetrize {
+        let symmetric = symmetrize(graph, memory_usage)?;
+        write_graph(&symmetric, width, &args, "Writing the symmetrized graph...")?;
+    } else {
+        write_graph(graph, width, &args, "Writing the graph...")?;
+    }
+
+    log::info!(
+        "The conversion took {:.3} seconds",
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+/// Writes `graph` to the destination of `args` in the bin format, restricted
+/// to the selected nodes (if any), with the given node width, logging `message`
+/// when the scan of the graph starts.
+fn write_graph(
+    graph: &impl SequentialGraph,
+    width: NodeWidth,
+    args: &CliArgs,
+    message: &str,
+) -> Result<()> {
+    let selection = args.selection.select(graph)?;
+    if let Some(selection) = &selection {
+        selection.write_ids(append_extension(&args.dst, "ids"))?;
+    }
 
     let mut pl = ProgressLogger::default();
     // log every five minutes
@@ -291,18 +274,192 @@ pub fn main(submatches: &ArgMatches) -> Result<()> {
         .expected_updates(Some(graph.num_nodes()));
 
     let temp_dir = &tempfile::tempdir()?;
-    let mut bin = BinGraphWriter::new(temp_dir.path());
+    let mut bin = BinGraphWriter::new(temp_dir.path(), width)?;
+    pl.start(message);
     let mut iter = graph.iter();
-    while let Some((_true_node_id, true_succ)) = iter.next() {
-        bin.add_list(true_succ)?;
+    while let Some((true_node_id, true_succ)) = iter.next() {
+        match &selection {
+            Some(selection) => {
+                if let Some((_node, successors)) = selection.map(true_node_id, true_succ) {
+                    bin.add_list(successors)?;
+                }
+            }
+            None => bin.add_list(true_succ)?,
+        }
         pl.update();
     }
     pl.done();
-    bin.write(args.dst)?;
+    bin.write(args.dst.clone())
+}
+
+/// Converts the graph using the random-access `BvGraph` (see [`to_bin_parallel`]).
+pub fn to_bin_parallel_bvgraph<E: Endianness + 'static>(args: CliArgs) -> Result<()>
+where
+    MmapHelper<u32>: CodesReaderFactoryHelper<E>,
+    for<'a> LoadModeCodesReader<'a, E, Mmap>: BitSeek,
+{
+    let graph = BvGraph::with_basename(&args.src)
+        .endianness::<E>()
+        .load()
+        .with_context(|| {
+            format!(
+                "Failed to load the random-access graph {:?}, the parallel mode requires the .ef file",
+                args.src.to_string_lossy()
+            )
+        })?;
+    to_bin_parallel(&graph, args)
+}
+
+/// Converts a random-access graph: the offsets are computed from the outdegrees,
+/// then the nodes are split in chunks with roughly the same number of arcs, which
+/// are decoded in parallel and written directly at their final position in the
+/// pre-sized output file.
+pub fn to_bin_parallel<G: RandomAccessGraph + Sync>(graph: &G, args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    create_parent_dir(&args.dst)?;
+
+    let num_nodes = graph.num_nodes();
+    let width = NodeWidth::resolve(args.width, num_nodes)?;
+
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads.unwrap_or(0))
+        .build()?;
+
+    thread_pool.install(|| {
+        // the offsets are the prefix sums of the outdegrees
+        let mut offsets = (0..num_nodes)
+            .into_par_iter()
+            .map(|node| graph.outdegree(node) as u64)
+            .collect::<Vec<_>>();
+        let mut num_arcs = 0;
+        for offset in offsets.iter_mut() {
+            let outdegree = *offset;
+            *offset = num_arcs;
+            num_arcs += outdegree;
+        }
+        offsets.push(num_arcs);
+
+        // write the header and the offsets, reserving the space for the arcs
+        let arcs_start = (width.header_len() + 8 * offsets.len()) as u64;
+        let file = File::create(&args.dst)?;
+        file.set_len(arcs_start + width.bytes() as u64 * num_arcs)
+            .context("Failed to allocate the output file")?;
+        let mut writer = BufWriter::new(file);
+        // Fingerprint of the simple uncompressed graph format
+        writer
+            .write_all(&width.fingerprint().to_le_bytes())
+            .context("Failed to write fingerprint")?;
+        width
+            .write_node(&mut writer, num_nodes)
+            .context("Failed to write number of nodes")?;
+        for offset in offsets.iter() {
+            writer
+                .write_all(&offset.to_le_bytes())
+                .context("Failed to write offset")?;
+        }
+        writer.flush().context("Failed to flush output file")?;
+        drop(writer);
+
+        // split the nodes in chunks with (roughly) the same number of arcs
+        let num_chunks = rayon::current_num_threads() * CHUNKS_PER_THREAD;
+        let arcs_per_chunk = num_arcs.div_ceil(num_chunks as u64).max(1);
+        let mut boundaries = (0..num_chunks as u64)
+            .map(|chunk| {
+                offsets
+                    .partition_point(|&offset| offset < chunk * arcs_per_chunk)
+                    .min(num_nodes)
+            })
+            .collect::<Vec<_>>();
+        boundaries.push(num_nodes);
+        boundaries.dedup();
+
+        let mut pl = ProgressLogger::default();
+        pl.log_interval(Duration::from_secs(60));
+        pl.item_name("node").expected_updates(Some(num_nodes));
+        pl.start(format!(
+            "Decoding {} nodes in {} chunks...",
+            num_nodes,
+            boundaries.len() - 1
+        ));
+        let pl = Mutex::new(pl);
+
+        boundaries
+            .par_windows(2)
+            .try_for_each(|chunk| -> Result<()> {
+                let (first_node, end_node) = (chunk[0], chunk[1]);
+                let mut file = OpenOptions::new()
+                    .write(true)
+                    .open(&args.dst)
+                    .context("Failed to open output file")?;
+                file.seek(SeekFrom::Start(
+                    arcs_start + width.bytes() as u64 * offsets[first_node],
+                ))
+                .context("Failed to seek to the arcs of the chunk")?;
+                let mut writer = BufWriter::new(file);
+
+                let mut iter = graph.iter_from(first_node).take(end_node - first_node);
+                while let Some((_node, successors)) = iter.next() {
+                    for successor in successors {
+                        width
+                            .write_node(&mut writer, successor)
+                            .context("Failed to write arc")?;
+                    }
+                }
+                writer.flush().context("Failed to flush output file")?;
+
+                pl.lock().unwrap().update_with_count(end_node - first_node);
+                Ok(())
+            })?;
+        pl.into_inner().unwrap().done();
+        Ok::<_, anyhow::Error>(())
+    })?;
 
     log::info!(
-        "The re-compression took {:.3} seconds",
+        "The parallel conversion took {:.3} seconds",
         start.elapsed().as_secs_f64()
     );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bin_graph::BinHeader;
+    use std::io::Cursor;
+
+    const LISTS: [&[usize]; 4] = [&[1, 3], &[], &[0, 1, 2, 3], &[3]];
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        for width in [NodeWidth::U32, NodeWidth::U64] {
+            let temp_dir = tempfile::tempdir()?;
+            let path = temp_dir.path().join("graph.bin");
+            let mut writer = BinGraphWriter::new(temp_dir.path(), width)?;
+            for successors in LISTS {
+                writer.add_list(successors.iter().copied())?;
+            }
+            writer.write(path.clone())?;
+
+            let bytes = std::fs::read(&path)?;
+            let header = BinHeader::read(&mut Cursor::new(&bytes), bytes.len() as u64)?;
+            assert_eq!(header.width, width);
+            assert_eq!(header.num_nodes, LISTS.len());
+            assert_eq!(header.num_arcs, 7);
+
+            let graph = BinGraphMmap::load(&path)?;
+            for (node, successors) in LISTS.iter().enumerate() {
+                assert_eq!(graph.successors(node).collect::<Vec<_>>(), *successors);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_node_too_large() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let mut writer = BinGraphWriter::new(temp_dir.path(), NodeWidth::U32)?;
+        assert!(writer.add_list([u32::MAX as usize + 1]).is_err());
+        Ok(())
+    }
+}
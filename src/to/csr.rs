@@ -0,0 +1,103 @@
+use anyhow::{Context, Result};
+
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use dsi_progress_logger::prelude::*;
+use lender::Lender;
+use std::{path::PathBuf, time::Duration};
+use webgraph::prelude::*;
+
+use crate::{
+    dispatch_graph,
+    npy::{Dtype, NpyWriter},
+};
+
+pub const COMMAND_NAME: &str = "csr";
+
+#[derive(Args, Debug)]
+#[command(about = "Decompresses a BvGraph to the CSR arrays indptr.npy and indices.npy, which can be loaded as a scipy.sparse.csr_matrix.", long_about = None)]
+pub struct CliArgs {
+    /// The basename of the source graph, or the path of a graph in the bin format.
+    pub src: PathBuf,
+    /// The directory where indptr.npy and indices.npy are written.
+    pub dst: PathBuf,
+
+    /// The data type of the offsets of the adjacency lists.
+    #[arg(long, value_enum, default_value = "int64")]
+    pub indptr_dtype: Dtype,
+
+    /// The data type of the successors.
+    #[arg(long, value_enum, default_value = "int32")]
+    pub indices_dtype: Dtype,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    dispatch_graph!(&args.src, to_csr(args))
+}
+
+pub fn to_csr(graph: &impl SequentialGraph, args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    std::fs::create_dir_all(&args.dst).with_context(|| {
+        format!(
+            "Failed to create the directory {:?}",
+            args.dst.to_string_lossy()
+        )
+    })?;
+
+    // check the data types before writing, as on a large graph an overflow
+    // would otherwise be found after writing gigabytes of values
+    let num_nodes = graph.num_nodes() as u64;
+    anyhow::ensure!(
+        num_nodes.saturating_sub(1) <= args.indices_dtype.max(),
+        "The successors of a graph with {} nodes do not fit in {:?}, use --indices-dtype int64",
+        num_nodes,
+        args.indices_dtype
+    );
+    if let Some(num_arcs) = graph.num_arcs_hint() {
+        anyhow::ensure!(
+            num_arcs <= args.indptr_dtype.max(),
+            "The offsets of a graph with {} arcs do not fit in {:?}, use --indptr-dtype int64",
+            num_arcs,
+            args.indptr_dtype
+        );
+    }
+
+    let mut indptr = NpyWriter::create(args.dst.join("indptr.npy"), args.indptr_dtype)?;
+    let mut indices = NpyWriter::create(args.dst.join("indices.npy"), args.indices_dtype)?;
+
+    let mut pl = ProgressLogger::default();
+    pl.log_interval(Duration::from_secs(60));
+    pl.item_name("node")
+        .expected_updates(Some(graph.num_nodes()));
+    pl.start("Writing the CSR arrays...");
+
+    let mut num_arcs = 0;
+    let mut iter = graph.iter();
+    while let Some((_node, successors)) = iter.next() {
+        indptr.push(num_arcs).context("Failed to write offset")?;
+        for successor in successors {
+            indices
+                .push(successor as u64)
+                .context("Failed to write successor")?;
+            num_arcs += 1;
+        }
+        pl.light_update();
+    }
+    indptr.push(num_arcs).context("Failed to write offset")?;
+    pl.done();
+
+    indptr.finish()?;
+    indices.finish()?;
+
+    log::info!(
+        "The conversion took {:.3} seconds",
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
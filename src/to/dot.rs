@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use std::{io::Write, path::PathBuf};
+
+use crate::to::{
+    output::OutputArgs,
+    subgraph::{self, SubgraphArgs},
+};
+
+pub const COMMAND_NAME: &str = "dot";
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Exports a small subgraph of a BvGraph to the Graphviz DOT format.",
+    long_about = "Exports the subgraph induced by a selection of nodes (a range, a ball around some seeds or a list read from a file) to the Graphviz DOT format, for visualization. For a BvGraph, the random-access graph is used, so the .ef file is required."
+)]
+pub struct CliArgs {
+    /// The basename of the source graph, or the path of a graph in the bin format.
+    pub src: PathBuf,
+
+    #[clap(flatten)]
+    pub output: OutputArgs,
+
+    #[clap(flatten)]
+    pub subgraph: SubgraphArgs,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    to_dot(args)
+}
+
+pub fn to_dot(args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    let subgraph = subgraph::extract(&args.src, &args.subgraph)?;
+    let names = subgraph.attribute_names();
+
+    let mut output = args.output.create()?;
+    writeln!(output, "digraph {{").context("Failed to write header")?;
+    for index in 0..subgraph.nodes.len() {
+        write!(output, "  {}", subgraph.id(index)).context("Failed to write node")?;
+        let values = subgraph.attributes(index);
+        if !values.is_empty() {
+            let attributes = names
+                .iter()
+                .zip(values)
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>();
+            write!(output, " [{}]", attributes.join(", ")).context("Failed to write node")?;
+        }
+        writeln!(output, ";").context("Failed to write node")?;
+    }
+    for &(src, dst) in &subgraph.arcs {
+        writeln!(output, "  {} -> {};", subgraph.id(src), subgraph.id(dst))
+            .context("Failed to write arc")?;
+    }
+    writeln!(output, "}}").context("Failed to write footer")?;
+    output.finish()?;
+
+    log::info!(
+        "Exported {} nodes and {} arcs in {:.3} seconds",
+        subgraph.nodes.len(),
+        subgraph.arcs.len(),
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
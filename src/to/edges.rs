@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use dsi_progress_logger::prelude::*;
+use lender::Lender;
+use std::{io::Write, path::PathBuf, time::Duration};
+use webgraph::prelude::*;
+
+use crate::{
+    dispatch_graph,
+    to::{output::OutputArgs, selection::SelectionArgs},
+    utils::append_extension,
+};
+
+pub const COMMAND_NAME: &str = "edges";
+
+#[derive(Args, Debug)]
+#[command(about = "Decompresses a BvGraph to a tab-separated edge list, with one arc per line.", long_about = None)]
+pub struct CliArgs {
+    /// The basename of the source graph, or the path of a graph in the bin format.
+    pub src: PathBuf,
+
+    #[clap(flatten)]
+    pub output: OutputArgs,
+
+    #[clap(flatten)]
+    pub selection: SelectionArgs,
+
+    /// Write a "src\tdst" header line before the arcs.
+    #[arg(long)]
+    pub header: bool,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    dispatch_graph!(&args.src, to_edges(args))
+}
+
+pub fn to_edges(graph: &impl SequentialGraph, args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    let selection = args.selection.select(graph)?;
+    if let Some(selection) = &selection {
+        let dst = args
+            .output
+            .dst
+            .as_ref()
+            .context("The selection of nodes requires an output path")?;
+        selection.write_ids(append_extension(dst, "ids"))?;
+    }
+
+    let mut output = args.output.create()?;
+    if args.header {
+        writeln!(output, "src\tdst").context("Failed to write header")?;
+    }
+
+    let mut pl = ProgressLogger::default();
+    pl.log_interval(Duration::from_secs(60));
+    pl.item_name("node")
+        .expected_updates(Some(graph.num_nodes()));
+    pl.start("Writing the arcs...");
+
+    let mut iter = graph.iter();
+    while let Some((node, successors)) = iter.next() {
+        match &selection {
+            Some(selection) => {
+                if let Some((node, successors)) = selection.map(node, successors) {
+                    write_arcs(&mut output, node, successors)?;
+                }
+            }
+            None => write_arcs(&mut output, node, successors)?,
+        }
+        pl.light_update();
+    }
+    pl.done();
+    output.finish()?;
+
+    log::info!(
+        "The conversion took {:.3} seconds",
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+/// Writes the arcs from `node` to its `successors`, one per line.
+fn write_arcs(
+    output: &mut impl Write,
+    node: usize,
+    successors: impl IntoIterator<Item = usize>,
+) -> Result<()> {
+    for successor in successors {
+        writeln!(output, "{}\t{}", node, successor).context("Failed to write arc")?;
+    }
+    Ok(())
+}
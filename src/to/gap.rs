@@ -0,0 +1,185 @@
+use anyhow::{Context, Result};
+
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use dsi_progress_logger::prelude::*;
+use lender::Lender;
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use webgraph::{prelude::*, transform::transpose, utils::MemoryUsage};
+
+use crate::{
+    dispatch_graph,
+    utils::{create_parent_dir, symmetrize},
+};
+
+pub const COMMAND_NAME: &str = "gap";
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Decompresses a BvGraph to the .sg serialized graph format of the GAP Benchmark Suite.",
+    long_about = "Decompresses a BvGraph to the .sg serialized graph format of the GAP Benchmark Suite: a directed flag (1 byte), the number of arcs and the number of nodes (8 bytes each), the offsets (8 bytes each) and the successors (4 bytes each), followed, for directed graphs, by the offsets and the successors of the transposed graph. All the integers are written in little endian. The graph is written as directed, unless --undirected is given, in which case it is symmetrized first."
+)]
+pub struct CliArgs {
+    /// The basename of the source graph, or the path of a graph in the bin format.
+    pub src: PathBuf,
+    /// The output path of the serialized graph.
+    pub dst: PathBuf,
+
+    /// Write an undirected graph, symmetrizing the graph (removing self-loops and
+    /// duplicate arcs) with external-memory sorting. Otherwise the graph is written
+    /// as directed, including the transposed (in-arcs) CSR computed with
+    /// external-memory sorting.
+    #[arg(short, long)]
+    pub undirected: bool,
+
+    /// The number of arcs sorted in memory in each batch when transposing or
+    /// symmetrizing the graph.
+    #[arg(short, long, default_value = "10000000")]
+    pub batch_size: usize,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    dispatch_graph!(&args.src, to_gap(args))
+}
+
+pub fn to_gap(graph: &impl SequentialGraph, args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    create_parent_dir(&args.dst)?;
+
+    let num_nodes = graph.num_nodes();
+    anyhow::ensure!(
+        num_nodes as u64 <= i32::MAX as u64,
+        "GAP stores nodes as 32-bit signed integers, but the graph has {} nodes",
+        num_nodes
+    );
+    let memory_usage = MemoryUsage::BatchSize(args.batch_size);
+
+    let mut pl = ProgressLogger::default();
+    pl.log_interval(Duration::from_secs(60));
+    pl.item_name("node").expected_updates(Some(num_nodes));
+
+    if args.undirected {
+        let symmetric = symmetrize(graph, memory_usage)?;
+
+        // the header contains the number of arcs, which is known only
+        // after the graph has been symmetrized
+        pl.start("Counting the arcs of the symmetrized graph...");
+        let mut num_arcs = 0;
+        let mut iter = symmetric.iter();
+        while let Some((_node, successors)) = iter.next() {
+            num_arcs += successors.into_iter().count() as u64;
+            pl.light_update();
+        }
+        pl.done();
+
+        write_header(&args.dst, false, num_nodes, num_arcs)?;
+        pl.start("Writing the symmetrized graph...");
+        write_csr(&symmetric, &args.dst, HEADER_LEN, num_arcs, &mut pl)?;
+        pl.done();
+    } else {
+        let num_arcs = graph
+            .num_arcs_hint()
+            .context("The number of arcs is missing from the properties of the graph")?;
+        write_header(&args.dst, true, num_nodes, num_arcs)?;
+
+        pl.start("Writing the graph...");
+        write_csr(graph, &args.dst, HEADER_LEN, num_arcs, &mut pl)?;
+        pl.done();
+
+        let transposed = transpose(graph, memory_usage).context("Failed to transpose the graph")?;
+        // the transposed CSR follows the offsets and the successors of the graph
+        let csr_len = 8 * (num_nodes as u64 + 1) + 4 * num_arcs;
+        pl.start("Writing the transposed graph...");
+        write_csr(
+            &transposed,
+            &args.dst,
+            HEADER_LEN + csr_len,
+            num_arcs,
+            &mut pl,
+        )?;
+        pl.done();
+    }
+
+    log::info!(
+        "The conversion took {:.3} seconds",
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+/// The length of the header: the directed flag, the number of arcs and the number of nodes.
+const HEADER_LEN: u64 = 1 + 8 + 8;
+
+/// Creates the file at `path` and writes the header.
+fn write_header(path: &Path, directed: bool, num_nodes: usize, num_arcs: u64) -> Result<()> {
+    let mut header = BufWriter::new(
+        File::create(path)
+            .with_context(|| format!("Failed to create the file {:?}", path.to_string_lossy()))?,
+    );
+    header.write_all(&[directed as u8])?;
+    header.write_all(&(num_arcs as i64).to_le_bytes())?;
+    header.write_all(&(num_nodes as i64).to_le_bytes())?;
+    header.flush().context("Failed to write header")?;
+    Ok(())
+}
+
+/// Writes the offsets and the successors of `graph` starting at byte `position`
+/// of the file at `path`, checking that the graph has `num_arcs` arcs.
+fn write_csr(
+    graph: &impl SequentialGraph,
+    path: &Path,
+    position: u64,
+    num_arcs: u64,
+    pl: &mut impl ProgressLog,
+) -> Result<()> {
+    let writer_at = |position: u64| -> Result<BufWriter<File>> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(path)
+            .context("Failed to open output file")?;
+        file.seek(SeekFrom::Start(position))?;
+        Ok(BufWriter::new(file))
+    };
+    // the successors start right after the N+1 offsets
+    let mut offsets = writer_at(position)?;
+    let mut successors = writer_at(position + 8 * (graph.num_nodes() as u64 + 1))?;
+
+    let mut offset = 0u64;
+    let mut iter = graph.iter();
+    while let Some((_node, succ)) = iter.next() {
+        offsets
+            .write_all(&(offset as i64).to_le_bytes())
+            .context("Failed to write offset")?;
+        for successor in succ {
+            successors
+                .write_all(&(successor as i32).to_le_bytes())
+                .context("Failed to write successor")?;
+            offset += 1;
+        }
+        pl.light_update();
+    }
+    offsets
+        .write_all(&(offset as i64).to_le_bytes())
+        .context("Failed to write offset")?;
+
+    anyhow::ensure!(
+        offset == num_arcs,
+        "The properties declare {} arcs, but the graph has {}",
+        num_arcs,
+        offset
+    );
+    offsets.flush().context("Failed to flush offsets")?;
+    successors.flush().context("Failed to flush successors")?;
+    Ok(())
+}
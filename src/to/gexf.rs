@@ -0,0 +1,118 @@
+use anyhow::{Context, Result};
+
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use std::{io::Write, path::PathBuf};
+
+use crate::to::{
+    output::OutputArgs,
+    subgraph::{self, SubgraphArgs},
+};
+
+pub const COMMAND_NAME: &str = "gexf";
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Exports a small subgraph of a BvGraph to the GEXF format.",
+    long_about = "Exports the subgraph induced by a selection of nodes (a range, a ball around some seeds or a list read from a file) to the GEXF 1.3 format, for visualization with Gephi. For a BvGraph, the random-access graph is used, so the .ef file is required."
+)]
+pub struct CliArgs {
+    /// The basename of the source graph, or the path of a graph in the bin format.
+    pub src: PathBuf,
+
+    #[clap(flatten)]
+    pub output: OutputArgs,
+
+    #[clap(flatten)]
+    pub subgraph: SubgraphArgs,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    to_gexf(args)
+}
+
+pub fn to_gexf(args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    let subgraph = subgraph::extract(&args.src, &args.subgraph)?;
+    let names = subgraph.attribute_names();
+
+    let mut output = args.output.create()?;
+    writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)
+        .context("Failed to write header")?;
+    writeln!(
+        output,
+        r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#
+    )
+    .context("Failed to write header")?;
+    writeln!(output, r#"  <graph defaultedgetype="directed">"#)
+        .context("Failed to write header")?;
+
+    // GEXF attributes are referred to by their position in the declaration
+    if !names.is_empty() {
+        writeln!(output, r#"    <attributes class="node">"#)
+            .context("Failed to write attributes")?;
+        for (id, name) in names.iter().enumerate() {
+            writeln!(
+                output,
+                r#"      <attribute id="{}" title="{}" type="long"/>"#,
+                id, name
+            )
+            .context("Failed to write attributes")?;
+        }
+        writeln!(output, "    </attributes>").context("Failed to write attributes")?;
+    }
+
+    writeln!(output, "    <nodes>").context("Failed to write nodes")?;
+    for index in 0..subgraph.nodes.len() {
+        let id = subgraph.id(index);
+        let values = subgraph.attributes(index);
+        if values.is_empty() {
+            writeln!(output, r#"      <node id="{0}" label="{0}"/>"#, id)
+                .context("Failed to write node")?;
+            continue;
+        }
+        writeln!(output, r#"      <node id="{0}" label="{0}">"#, id)
+            .context("Failed to write node")?;
+        writeln!(output, "        <attvalues>").context("Failed to write node")?;
+        for (attribute, value) in values.into_iter().enumerate() {
+            writeln!(
+                output,
+                r#"          <attvalue for="{}" value="{}"/>"#,
+                attribute, value
+            )
+            .context("Failed to write node")?;
+        }
+        writeln!(output, "        </attvalues>\n      </node>").context("Failed to write node")?;
+    }
+    writeln!(output, "    </nodes>").context("Failed to write nodes")?;
+
+    writeln!(output, "    <edges>").context("Failed to write arcs")?;
+    for (id, &(src, dst)) in subgraph.arcs.iter().enumerate() {
+        writeln!(
+            output,
+            r#"      <edge id="{}" source="{}" target="{}"/>"#,
+            id,
+            subgraph.id(src),
+            subgraph.id(dst)
+        )
+        .context("Failed to write arc")?;
+    }
+    writeln!(output, "    </edges>").context("Failed to write arcs")?;
+
+    writeln!(output, "  </graph>\n</gexf>").context("Failed to write footer")?;
+    output.finish()?;
+
+    log::info!(
+        "Exported {} nodes and {} arcs in {:.3} seconds",
+        subgraph.nodes.len(),
+        subgraph.arcs.len(),
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use std::{io::Write, path::PathBuf};
+
+use crate::to::{
+    output::OutputArgs,
+    subgraph::{self, SubgraphArgs},
+};
+
+pub const COMMAND_NAME: &str = "graphml";
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Exports a small subgraph of a BvGraph to the GraphML format.",
+    long_about = "Exports the subgraph induced by a selection of nodes (a range, a ball around some seeds or a list read from a file) to the GraphML format, for visualization with tools such as yEd, Cytoscape or NetworkX. For a BvGraph, the random-access graph is used, so the .ef file is required."
+)]
+pub struct CliArgs {
+    /// The basename of the source graph, or the path of a graph in the bin format.
+    pub src: PathBuf,
+
+    #[clap(flatten)]
+    pub output: OutputArgs,
+
+    #[clap(flatten)]
+    pub subgraph: SubgraphArgs,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    to_graphml(args)
+}
+
+pub fn to_graphml(args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    let subgraph = subgraph::extract(&args.src, &args.subgraph)?;
+    let names = subgraph.attribute_names();
+
+    let mut output = args.output.create()?;
+    writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)
+        .context("Failed to write header")?;
+    writeln!(
+        output,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )
+    .context("Failed to write header")?;
+    for name in &names {
+        writeln!(
+            output,
+            r#"  <key id="{0}" for="node" attr.name="{0}" attr.type="long"/>"#,
+            name
+        )
+        .context("Failed to write key")?;
+    }
+    writeln!(output, r#"  <graph id="G" edgedefault="directed">"#)
+        .context("Failed to write header")?;
+
+    for index in 0..subgraph.nodes.len() {
+        let values = subgraph.attributes(index);
+        if values.is_empty() {
+            writeln!(output, r#"    <node id="n{}"/>"#, subgraph.id(index))
+                .context("Failed to write node")?;
+            continue;
+        }
+        writeln!(output, r#"    <node id="n{}">"#, subgraph.id(index))
+            .context("Failed to write node")?;
+        for (name, value) in names.iter().zip(values) {
+            writeln!(output, r#"      <data key="{}">{}</data>"#, name, value)
+                .context("Failed to write node")?;
+        }
+        writeln!(output, "    </node>").context("Failed to write node")?;
+    }
+    for &(src, dst) in &subgraph.arcs {
+        writeln!(
+            output,
+            r#"    <edge source="n{}" target="n{}"/>"#,
+            subgraph.id(src),
+            subgraph.id(dst)
+        )
+        .context("Failed to write arc")?;
+    }
+
+    writeln!(output, "  </graph>\n</graphml>").context("Failed to write footer")?;
+    output.finish()?;
+
+    log::info!(
+        "Exported {} nodes and {} arcs in {:.3} seconds",
+        subgraph.nodes.len(),
+        subgraph.arcs.len(),
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
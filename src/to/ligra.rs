@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use dsi_progress_logger::prelude::*;
+use lender::Lender;
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use webgraph::prelude::*;
+
+use crate::{
+    bin_graph::NodeWidth,
+    dispatch_graph,
+    to::bin::{BinGraphParts, BinGraphWriter},
+    utils::{append_extension, create_parent_dir},
+};
+
+pub const COMMAND_NAME: &str = "ligra";
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Decompresses a BvGraph to the AdjacencyGraph format used by Ligra and GBBS.",
+    long_about = "Decompresses a BvGraph to the AdjacencyGraph format used by Ligra and GBBS. The text format contains the header \"AdjacencyGraph\", the number of nodes n, the number of arcs m, the n offsets and the m successors, one per line. The binary format is made of three files: <dst>.config with the number of nodes, <dst>.idx with the offsets as 8-byte integers and <dst>.adj with the successors as 4-byte integers."
+)]
+pub struct CliArgs {
+    /// The basename of the source graph, or the path of a graph in the bin format.
+    pub src: PathBuf,
+    /// The output path of the text graph, or the basename of the binary graph.
+    pub dst: PathBuf,
+
+    /// Write the binary format (.config, .idx and .adj files) instead of the text one.
+    #[arg(short, long)]
+    pub binary: bool,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    dispatch_graph!(&args.src, to_ligra(args))
+}
+
+pub fn to_ligra(graph: &impl SequentialGraph, args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    create_parent_dir(&args.dst)?;
+
+    let num_nodes = graph.num_nodes();
+
+    // the binary format stores the offsets and the arcs exactly as the temporary
+    // files of the bin format, while the text format needs all the offsets before
+    // the arcs, so they are buffered in the temporary files and then converted
+    let temp_dir = tempfile::tempdir()?;
+    let mut writer = if args.binary {
+        // Ligra reads the successors as 4-byte unsigned integers
+        NodeWidth::U32.check(num_nodes)?;
+        BinGraphWriter::with_files(
+            append_extension(&args.dst, "idx"),
+            append_extension(&args.dst, "adj"),
+            NodeWidth::U32,
+        )?
+    } else {
+        BinGraphWriter::with_files(
+            temp_dir.path().join("offsets.tmp"),
+            temp_dir.path().join("arcs.tmp"),
+            NodeWidth::U64,
+        )?
+    };
+
+    let mut pl = ProgressLogger::default();
+    pl.log_interval(Duration::from_secs(60));
+    pl.item_name("node").expected_updates(Some(num_nodes));
+    pl.start("Decoding the graph...");
+
+    let mut iter = graph.iter();
+    while let Some((_node, successors)) = iter.next() {
+        writer.add_list(successors)?;
+        pl.light_update();
+    }
+    pl.done();
+    let parts = writer.into_parts()?;
+
+    if args.binary {
+        let mut config = File::create(append_extension(&args.dst, "config"))
+            .context("Failed to create the config file")?;
+        writeln!(config, "{}", parts.node_count).context("Failed to write the config file")?;
+    } else {
+        write_text(&parts, &args.dst)?;
+    }
+
+    log::info!(
+        "The conversion took {:.3} seconds",
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+/// Writes the text AdjacencyGraph format converting the offsets and
+/// the (8-byte) arcs in `parts`.
+fn write_text(parts: &BinGraphParts, path: &Path) -> Result<()> {
+    let mut output = BufWriter::new(
+        File::create(path)
+            .with_context(|| format!("Failed to create the file {:?}", path.to_string_lossy()))?,
+    );
+    writeln!(output, "AdjacencyGraph")?;
+    writeln!(output, "{}", parts.node_count)?;
+    writeln!(output, "{}", parts.arc_count)?;
+
+    for (file, count) in [
+        (&parts.offset_file, parts.node_count),
+        (&parts.arc_file, parts.arc_count),
+    ] {
+        let mut reader =
+            BufReader::new(File::open(file).with_context(|| {
+                format!("Failed to open the file {:?}", file.to_string_lossy())
+            })?);
+        let mut bytes = [0u8; 8];
+        for _ in 0..count {
+            reader
+                .read_exact(&mut bytes)
+                .context("Failed to read temporary file")?;
+            writeln!(output, "{}", u64::from_le_bytes(bytes)).context("Failed to write graph")?;
+        }
+    }
+
+    output.flush().context("Failed to flush output file")?;
+    Ok(())
+}
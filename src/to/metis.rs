@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use dsi_progress_logger::prelude::*;
+use lender::Lender;
+use std::{io::Write, path::PathBuf, time::Duration};
+use webgraph::{prelude::*, utils::MemoryUsage};
+
+use crate::{dispatch_graph, to::output::OutputArgs, utils::symmetrize};
+
+pub const COMMAND_NAME: &str = "metis";
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Decompresses a BvGraph to the METIS graph format used by partitioners.",
+    long_about = "Decompresses a BvGraph to the METIS graph format used by partitioners such as METIS, KaHIP and Mt-KaHyPar. As METIS graphs are undirected, the graph is symmetrized, removing self-loops and duplicate edges, and the nodes are numbered starting from 1."
+)]
+pub struct CliArgs {
+    /// The basename of the source graph, or the path of a graph in the bin format.
+    pub src: PathBuf,
+
+    #[clap(flatten)]
+    pub output: OutputArgs,
+
+    /// Use the outdegree in the original graph as the weight of each vertex.
+    #[arg(long)]
+    pub vertex_weights: bool,
+
+    /// The number of arcs sorted in memory in each batch when symmetrizing the graph.
+    #[arg(short, long, default_value = "10000000")]
+    pub batch_size: usize,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    dispatch_graph!(&args.src, to_metis(args))
+}
+
+pub fn to_metis(graph: &impl SequentialGraph, args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    let num_nodes = graph.num_nodes();
+    let symmetric = symmetrize(graph, MemoryUsage::BatchSize(args.batch_size))?;
+
+    let mut pl = ProgressLogger::default();
+    pl.log_interval(Duration::from_secs(60));
+    pl.item_name("node").expected_updates(Some(num_nodes));
+
+    // the header contains the number of edges, which is known only
+    // after the graph has been symmetrized
+    pl.start("Counting the edges of the symmetrized graph...");
+    let mut num_arcs = 0;
+    let mut iter = symmetric.iter();
+    while let Some((_node, successors)) = iter.next() {
+        num_arcs += successors.into_iter().count() as u64;
+        pl.light_update();
+    }
+    pl.done();
+
+    // the "10" format flag signals that each line starts with the vertex weight
+    let format = if args.vertex_weights { " 10" } else { "" };
+    let mut output = args.output.create()?;
+    writeln!(output, "{} {}{}", num_nodes, num_arcs / 2, format)
+        .context("Failed to write header")?;
+
+    // the original graph is scanned in lockstep to recover the outdegrees
+    let mut original_iter = graph.iter();
+
+    pl.start("Writing the symmetrized graph...");
+    let mut iter = symmetric.iter();
+    while let Some((_node, successors)) = iter.next() {
+        if args.vertex_weights {
+            let (_node, original_successors) = original_iter.next().unwrap();
+            let outdegree = original_successors.into_iter().count();
+            write!(output, "{}", outdegree).context("Failed to write vertex weight")?;
+        }
+        // METIS nodes are 1-based, and the line of a vertex without
+        // weight starts directly with its neighbours
+        let mut separator = if args.vertex_weights { " " } else { "" };
+        for successor in successors {
+            write!(output, "{}{}", separator, successor + 1).context("Failed to write edge")?;
+            separator = " ";
+        }
+        writeln!(output).context("Failed to write node")?;
+        pl.light_update();
+    }
+    pl.done();
+    output.finish()?;
+
+    log::info!(
+        "The conversion took {:.3} seconds",
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
@@ -1,23 +1,68 @@
 use anyhow::Result;
 use clap::{ArgMatches, Command};
 
+pub mod adj;
+#[cfg(feature = "arrow")]
+pub mod arrow;
 pub mod bin;
+pub mod csr;
+pub mod dot;
+pub mod edges;
+pub mod gap;
+pub mod gexf;
+pub mod graphml;
+pub mod ligra;
+pub mod metis;
+pub mod mtx;
+mod output;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+mod selection;
+mod subgraph;
 
 pub const COMMAND_NAME: &str = "to";
 
 pub fn cli(command: Command) -> Command {
     let sub_command = Command::new(COMMAND_NAME)
-        .about("Transform a BvGraph to a target format.")
+        .about("Transform a BvGraph (or a graph in the bin format) to a target format.")
         .subcommand_required(true)
         .arg_required_else_help(true)
         .allow_external_subcommands(true);
     let sub_command = bin::cli(sub_command);
+    let sub_command = edges::cli(sub_command);
+    let sub_command = adj::cli(sub_command);
+    let sub_command = csr::cli(sub_command);
+    let sub_command = metis::cli(sub_command);
+    let sub_command = mtx::cli(sub_command);
+    let sub_command = ligra::cli(sub_command);
+    let sub_command = gap::cli(sub_command);
+    let sub_command = dot::cli(sub_command);
+    let sub_command = graphml::cli(sub_command);
+    let sub_command = gexf::cli(sub_command);
+    #[cfg(feature = "parquet")]
+    let sub_command = parquet::cli(sub_command);
+    #[cfg(feature = "arrow")]
+    let sub_command = arrow::cli(sub_command);
     command.subcommand(sub_command.display_order(0))
 }
 
 pub fn main(submatches: &ArgMatches) -> Result<()> {
     match submatches.subcommand() {
         Some((bin::COMMAND_NAME, sub_m)) => bin::main(sub_m),
+        Some((edges::COMMAND_NAME, sub_m)) => edges::main(sub_m),
+        Some((adj::COMMAND_NAME, sub_m)) => adj::main(sub_m),
+        Some((csr::COMMAND_NAME, sub_m)) => csr::main(sub_m),
+        Some((metis::COMMAND_NAME, sub_m)) => metis::main(sub_m),
+        Some((mtx::COMMAND_NAME, sub_m)) => mtx::main(sub_m),
+        Some((ligra::COMMAND_NAME, sub_m)) => ligra::main(sub_m),
+        Some((gap::COMMAND_NAME, sub_m)) => gap::main(sub_m),
+        Some((dot::COMMAND_NAME, sub_m)) => dot::main(sub_m),
+        Some((graphml::COMMAND_NAME, sub_m)) => graphml::main(sub_m),
+        Some((gexf::COMMAND_NAME, sub_m)) => gexf::main(sub_m),
+        #[cfg(feature = "parquet")]
+        Some((parquet::COMMAND_NAME, sub_m)) => parquet::main(sub_m),
+        #[cfg(feature = "arrow")]
+        Some((arrow::COMMAND_NAME, sub_m)) => arrow::main(sub_m),
         Some((command_name, _)) => {
             eprintln!("Unknown command: {:?}", command_name);
             std::process::exit(1);
@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use dsi_progress_logger::prelude::*;
+use lender::Lender;
+use std::{io::Write, path::PathBuf, time::Duration};
+use webgraph::prelude::*;
+
+use crate::{dispatch_graph, to::output::OutputArgs};
+
+pub const COMMAND_NAME: &str = "mtx";
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Decompresses a BvGraph to the Matrix Market coordinate format.",
+    long_about = "Decompresses a BvGraph to the Matrix Market coordinate format, as a pattern matrix whose nonzero entries are the arcs of the graph, with 1-based row and column indices."
+)]
+pub struct CliArgs {
+    /// The basename of the source graph, or the path of a graph in the bin format.
+    pub src: PathBuf,
+
+    #[clap(flatten)]
+    pub output: OutputArgs,
+
+    /// Write a symmetric matrix, keeping only the arcs in the lower triangle
+    /// (i.e., from a node to a smaller or equal one): the graph must be symmetric.
+    #[arg(short, long)]
+    pub symmetric: bool,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    dispatch_graph!(&args.src, to_mtx(args))
+}
+
+pub fn to_mtx(graph: &impl SequentialGraph, args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    let num_nodes = graph.num_nodes();
+    let num_arcs = graph
+        .num_arcs_hint()
+        .context("The number of arcs is missing from the properties of the graph")?;
+
+    let mut pl = ProgressLogger::default();
+    pl.log_interval(Duration::from_secs(60));
+    pl.item_name("node").expected_updates(Some(num_nodes));
+
+    // a symmetric matrix stores only the lower triangle, whose size
+    // depends on the number of loops, so it has to be counted
+    let num_entries = if args.symmetric {
+        pl.start("Counting the entries of the lower triangle...");
+        let mut num_entries = 0;
+        let mut iter = graph.iter();
+        while let Some((node, successors)) = iter.next() {
+            num_entries += successors
+                .into_iter()
+                .filter(|&successor| successor <= node)
+                .count() as u64;
+            pl.light_update();
+        }
+        pl.done();
+        num_entries
+    } else {
+        num_arcs
+    };
+
+    let mut output = args.output.create()?;
+    let symmetry = if args.symmetric {
+        "symmetric"
+    } else {
+        "general"
+    };
+    writeln!(
+        output,
+        "%%MatrixMarket matrix coordinate pattern {}",
+        symmetry
+    )
+    .context("Failed to write header")?;
+    writeln!(output, "{} {} {}", num_nodes, num_nodes, num_entries)
+        .context("Failed to write size line")?;
+
+    pl.start("Writing the entries...");
+    let mut entries_written = 0;
+    let mut iter = graph.iter();
+    while let Some((node, successors)) = iter.next() {
+        for successor in successors {
+            if args.symmetric && successor > node {
+                continue;
+            }
+            writeln!(output, "{} {}", node + 1, successor + 1).context("Failed to write entry")?;
+            entries_written += 1;
+        }
+        pl.light_update();
+    }
+    pl.done();
+    output.finish()?;
+
+    anyhow::ensure!(
+        entries_written == num_entries,
+        "The header declares {} entries, but {} were written: the number of arcs in the properties is wrong",
+        num_entries,
+        entries_written
+    );
+
+    log::info!(
+        "The conversion took {:.3} seconds",
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
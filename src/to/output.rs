@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use flate2::write::GzEncoder;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use crate::utils::create_parent_dir;
+
+/// The compression applied to a textual output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+/// The destination of the textual formats: a file or stdout, optionally compressed.
+#[derive(Args, Debug)]
+pub struct OutputArgs {
+    /// The output path of the converted graph (defaults to stdout).
+    pub dst: Option<PathBuf>,
+
+    /// Compress the output with the given algorithm.
+    #[arg(short, long, value_enum, default_value = "none")]
+    pub compression: Compression,
+}
+
+impl OutputArgs {
+    /// Creates the output, with its parent directories if it is a file.
+    pub fn create(&self) -> Result<Output> {
+        let writer: Box<dyn Write> = match &self.dst {
+            Some(path) => {
+                create_parent_dir(path)?;
+                Box::new(File::create(path).with_context(|| {
+                    format!("Failed to create the file {:?}", path.to_string_lossy())
+                })?)
+            }
+            None => Box::new(std::io::stdout().lock()),
+        };
+        let writer = BufWriter::new(writer);
+        Ok(match self.compression {
+            Compression::None => Output::Plain(writer),
+            Compression::Gzip => {
+                Output::Gzip(GzEncoder::new(writer, flate2::Compression::default()))
+            }
+            Compression::Zstd => Output::Zstd(
+                zstd::Encoder::new(writer, 0).context("Failed to create the zstd encoder")?,
+            ),
+        })
+    }
+}
+
+/// A buffered, optionally compressed, output.
+///
+/// [`finish`](Output::finish) must be called after the last write, as it
+/// completes the compressed stream and reports the errors of the last flush.
+pub enum Output {
+    Plain(BufWriter<Box<dyn Write>>),
+    Gzip(GzEncoder<BufWriter<Box<dyn Write>>>),
+    Zstd(zstd::Encoder<'static, BufWriter<Box<dyn Write>>>),
+}
+
+impl Output {
+    /// Completes the compressed stream, if any, and flushes the output.
+    pub fn finish(self) -> Result<()> {
+        let mut writer = match self {
+            Output::Plain(writer) => writer,
+            Output::Gzip(encoder) => encoder.finish().context("Failed to complete gzip stream")?,
+            Output::Zstd(encoder) => encoder.finish().context("Failed to complete zstd stream")?,
+        };
+        writer.flush().context("Failed to flush output")?;
+        Ok(())
+    }
+}
+
+impl Write for Output {
+    #[inline(always)]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Output::Plain(writer) => writer.write(buf),
+            Output::Gzip(encoder) => encoder.write(buf),
+            Output::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Output::Plain(writer) => writer.flush(),
+            Output::Gzip(encoder) => encoder.flush(),
+            Output::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
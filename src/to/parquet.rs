@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+
+use ::parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression as ParquetCompression, ZstdLevel},
+    file::properties::WriterProperties,
+};
+use clap::{ArgMatches, Args, Command, FromArgMatches, ValueEnum};
+use dsi_progress_logger::prelude::*;
+use std::{fs::File, path::PathBuf, time::Duration};
+use webgraph::prelude::*;
+
+use crate::{
+    bin_graph::NodeWidth,
+    dispatch_graph,
+    to::arrow::{edge_schema, for_each_batch},
+    utils::create_parent_dir,
+};
+
+pub const COMMAND_NAME: &str = "parquet";
+
+/// The compression of the pages of the Parquet file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    None,
+    Snappy,
+    Zstd,
+}
+
+impl From<Compression> for ParquetCompression {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::None => ParquetCompression::UNCOMPRESSED,
+            Compression::Snappy => ParquetCompression::SNAPPY,
+            Compression::Zstd => ParquetCompression::ZSTD(ZstdLevel::default()),
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Decompresses a BvGraph to a Parquet file with a (src, dst) row for each arc.",
+    long_about = "Decompresses a BvGraph to a Parquet file containing a table with two columns, src and dst, and a row for each arc, written in row groups so that it can be queried directly by DuckDB, Polars or Spark."
+)]
+pub struct CliArgs {
+    /// The basename of the source graph, or the path of a graph in the bin format.
+    pub src: PathBuf,
+    /// The output path of the Parquet file.
+    pub dst: PathBuf,
+
+    /// The type of the columns: by default u32, unless the graph has more than 2^32 - 1 nodes.
+    #[arg(short, long, value_enum)]
+    pub width: Option<NodeWidth>,
+
+    /// The number of rows of each row group.
+    #[arg(short, long, default_value = "1048576")]
+    pub row_group_size: usize,
+
+    /// The compression of the pages.
+    #[arg(short, long, value_enum, default_value = "zstd")]
+    pub compression: Compression,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+
+    dispatch_graph!(&args.src, to_parquet(args))
+}
+
+pub fn to_parquet(graph: &impl SequentialGraph, args: CliArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    create_parent_dir(&args.dst)?;
+
+    let width = NodeWidth::resolve(args.width, graph.num_nodes())?;
+    let schema = edge_schema(width);
+
+    let properties = WriterProperties::builder()
+        .set_compression(args.compression.into())
+        .set_max_row_group_size(args.row_group_size)
+        .build();
+    let file = File::create(&args.dst)
+        .with_context(|| format!("Failed to create the file {:?}", args.dst.to_string_lossy()))?;
+    let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(properties))
+        .context("Failed to create the Parquet writer")?;
+
+    let mut pl = ProgressLogger::default();
+    pl.log_interval(Duration::from_secs(60));
+    pl.item_name("node")
+        .expected_updates(Some(graph.num_nodes()));
+    pl.start("Writing the arcs...");
+    // each batch fills exactly a row group
+    for_each_batch(
+        graph,
+        &schema,
+        width,
+        args.row_group_size,
+        &mut pl,
+        |batch| writer.write(&batch).context("Failed to write row group"),
+    )?;
+    pl.done();
+    writer
+        .close()
+        .context("Failed to finish the Parquet file")?;
+
+    log::info!(
+        "The conversion took {:.3} seconds",
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
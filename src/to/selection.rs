@@ -0,0 +1,177 @@
+use anyhow::{Context, Result};
+
+use clap::Args;
+use dsi_progress_logger::prelude::*;
+use lender::Lender;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use webgraph::prelude::*;
+
+use crate::{
+    to::subgraph::{parse_range, read_nodes_file},
+    utils::create_parent_dir,
+};
+
+/// The restriction of a conversion to a subset of the nodes.
+#[derive(Args, Debug)]
+pub struct SelectionArgs {
+    /// Restrict the output to a subset of the nodes, given as a range start..end or
+    /// as a file with a node per line. The nodes of the output are renumbered
+    /// consecutively, preserving their order, and the map from the original to the
+    /// new identifiers is written as "old\tnew" lines to the output path followed by .ids.
+    #[arg(long, value_parser = parse_nodes, requires = "dst")]
+    pub nodes: Option<NodeSet>,
+
+    /// Keep only the arcs between the selected nodes (i.e., write the induced subgraph).
+    /// Otherwise, all the arcs leaving the selected nodes are kept, and their
+    /// successors are added (with no arcs) to the nodes of the output.
+    #[arg(long, requires = "nodes")]
+    pub induced: bool,
+}
+
+/// A set of nodes given on the command line.
+#[derive(Clone, Debug)]
+pub enum NodeSet {
+    Range(Range<usize>),
+    File(PathBuf),
+}
+
+/// Parses a range if `nodes` contains `..`, and a path otherwise.
+fn parse_nodes(nodes: &str) -> Result<NodeSet, String> {
+    if nodes.contains("..") {
+        parse_range(nodes).map(NodeSet::Range)
+    } else {
+        Ok(NodeSet::File(PathBuf::from(nodes)))
+    }
+}
+
+/// A set of nodes that assigns to each of its elements its rank as the new identifier.
+#[derive(Clone)]
+enum RankedNodes {
+    Range(Range<usize>),
+    /// Sorted and without duplicates.
+    Sorted(Vec<usize>),
+}
+
+impl RankedNodes {
+    fn rank(&self, node: usize) -> Option<usize> {
+        match self {
+            RankedNodes::Range(range) => range.contains(&node).then(|| node - range.start),
+            RankedNodes::Sorted(nodes) => nodes.binary_search(&node).ok(),
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        match self {
+            RankedNodes::Range(range) => Box::new(range.clone()),
+            RankedNodes::Sorted(nodes) => Box::new(nodes.iter().copied()),
+        }
+    }
+}
+
+/// Maps the nodes of a graph to the nodes of the subgraph selected by [`SelectionArgs`].
+pub struct Selection {
+    selected: RankedNodes,
+    /// The nodes of the output: the selected nodes, plus their successors
+    /// if the subgraph is not induced.
+    output: RankedNodes,
+}
+
+impl SelectionArgs {
+    /// Returns the selection of the nodes of `graph`, or `None` if no selection was
+    /// requested. If the subgraph is not induced, the graph is scanned once to find
+    /// the successors of the selected nodes.
+    pub fn select(&self, graph: &impl SequentialGraph) -> Result<Option<Selection>> {
+        let Some(nodes) = &self.nodes else {
+            return Ok(None);
+        };
+        let num_nodes = graph.num_nodes();
+        let selected = match nodes {
+            NodeSet::Range(range) => {
+                anyhow::ensure!(
+                    range.end <= num_nodes,
+                    "The range {:?} exceeds the number of nodes ({})",
+                    range,
+                    num_nodes
+                );
+                RankedNodes::Range(range.clone())
+            }
+            NodeSet::File(path) => {
+                let mut nodes = read_nodes_file(path, num_nodes)?;
+                nodes.sort_unstable();
+                nodes.dedup();
+                RankedNodes::Sorted(nodes)
+            }
+        };
+
+        if self.induced {
+            return Ok(Some(Selection {
+                output: selected.clone(),
+                selected,
+            }));
+        }
+
+        let mut pl = ProgressLogger::default();
+        pl.log_interval(Duration::from_secs(60));
+        pl.item_name("node").expected_updates(Some(num_nodes));
+        pl.start("Collecting the successors of the selected nodes...");
+        let mut nodes = Vec::new();
+        let mut iter = graph.iter();
+        while let Some((node, successors)) = iter.next() {
+            if selected.rank(node).is_some() {
+                nodes.push(node);
+                nodes.extend(successors);
+            }
+            pl.light_update();
+        }
+        pl.done();
+        nodes.sort_unstable();
+        nodes.dedup();
+
+        Ok(Some(Selection {
+            selected,
+            output: RankedNodes::Sorted(nodes),
+        }))
+    }
+}
+
+impl Selection {
+    /// Maps a node of the original graph and its successors to the output, returning
+    /// `None` if the node is not part of it.
+    pub fn map(
+        &self,
+        node: usize,
+        successors: impl IntoIterator<Item = usize>,
+    ) -> Option<(usize, Vec<usize>)> {
+        let new_node = self.output.rank(node)?;
+        let new_successors = if self.selected.rank(node).is_some() {
+            successors
+                .into_iter()
+                .filter_map(|successor| self.output.rank(successor))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Some((new_node, new_successors))
+    }
+
+    /// Writes the map from the original to the new identifiers as "old\tnew" lines.
+    pub fn write_ids(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        create_parent_dir(path)?;
+        let mut writer =
+            BufWriter::new(File::create(path).with_context(|| {
+                format!("Failed to create the file {:?}", path.to_string_lossy())
+            })?);
+        for (new_node, node) in self.output.iter().enumerate() {
+            writeln!(writer, "{}\t{}", node, new_node).context("Failed to write id")?;
+        }
+        writer.flush().context("Failed to flush the ids file")?;
+        Ok(())
+    }
+}
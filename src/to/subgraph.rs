@@ -0,0 +1,295 @@
+use anyhow::{Context, Result};
+
+use clap::Args;
+use dsi_bitstream::{dispatch::factory::CodesReaderFactoryHelper, prelude::*};
+use dsi_progress_logger::prelude::*;
+use lender::Lender;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use webgraph::prelude::*;
+
+use crate::{
+    analyze::{component::BvGraphComponent, ConsumerDecoderFactory},
+    bin_graph::{is_bin_graph, BinGraphMmap},
+    dispatch_endianness,
+};
+
+/// The selection of a small subgraph to export, used by the visualization formats.
+#[derive(Args, Debug)]
+pub struct SubgraphArgs {
+    /// Select the nodes in the range start..end.
+    #[arg(long, value_parser = parse_range, conflicts_with_all = ["seeds", "nodes_file"])]
+    pub range: Option<Range<usize>>,
+
+    /// Select the nodes at distance at most --radius from the given (comma-separated) seeds.
+    #[arg(long, value_delimiter = ',', conflicts_with = "nodes_file")]
+    pub seeds: Option<Vec<usize>>,
+
+    /// The radius of the ball around the seeds.
+    #[arg(long, default_value_t = 1, requires = "seeds")]
+    pub radius: usize,
+
+    /// Select the nodes listed in a file, one per line.
+    #[arg(long)]
+    pub nodes_file: Option<PathBuf>,
+
+    /// Renumber the selected nodes starting from 0, keeping the original identifiers as an attribute.
+    #[arg(long)]
+    pub remap: bool,
+
+    /// Annotate the nodes with their outdegree and reference offset (requires a full scan of the graph).
+    #[arg(long)]
+    pub annotate: bool,
+
+    /// The maximum number of nodes that can be selected.
+    #[arg(long, default_value_t = 10_000)]
+    pub max_nodes: usize,
+}
+
+pub(crate) fn parse_range(range: &str) -> Result<Range<usize>, String> {
+    let (start, end) = range
+        .split_once("..")
+        .ok_or_else(|| format!("Invalid range {:?}, expected start..end", range))?;
+    let start = start.parse().map_err(|e| format!("Invalid start: {}", e))?;
+    let end = end.parse().map_err(|e| format!("Invalid end: {}", e))?;
+    Ok(start..end)
+}
+
+/// Reads the nodes listed in the file at `path`, one per line (ignoring empty
+/// lines), checking that they are smaller than `num_nodes`.
+pub(crate) fn read_nodes_file(path: &Path, num_nodes: usize) -> Result<Vec<usize>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the nodes file {:?}", path.to_string_lossy()))?;
+    let mut nodes = Vec::new();
+    for line in content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        let node = line
+            .parse::<usize>()
+            .with_context(|| format!("Invalid node {:?}", line))?;
+        anyhow::ensure!(node < num_nodes, "Invalid node {}", node);
+        nodes.push(node);
+    }
+    Ok(nodes)
+}
+
+/// A subgraph induced by a selection of nodes.
+pub struct Subgraph {
+    /// The selected nodes, sorted by their identifier in the original graph.
+    pub nodes: Vec<usize>,
+    /// The arcs among the selected nodes, as pairs of indices in `nodes`.
+    pub arcs: Vec<(usize, usize)>,
+    /// The outdegree and the reference offset in the original graph of each selected node.
+    annotations: Option<Vec<(usize, u64)>>,
+    remap: bool,
+}
+
+impl Subgraph {
+    /// Returns the map from the selected nodes to their indices.
+    fn index_of(&self) -> HashMap<usize, usize> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(index, &node)| (node, index))
+            .collect()
+    }
+
+    /// Returns the identifier to export for the selected node at `index`.
+    pub fn id(&self, index: usize) -> usize {
+        if self.remap {
+            index
+        } else {
+            self.nodes[index]
+        }
+    }
+
+    /// Returns the names of the attributes of the nodes.
+    pub fn attribute_names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if self.remap {
+            names.push("original_id");
+        }
+        if self.annotations.is_some() {
+            names.extend(["outdegree", "reference_offset"]);
+        }
+        names
+    }
+
+    /// Returns the values of the attributes of the selected node at `index`, in the
+    /// same order of [`attribute_names`](Subgraph::attribute_names).
+    pub fn attributes(&self, index: usize) -> Vec<u64> {
+        let mut values = Vec::new();
+        if self.remap {
+            values.push(self.nodes[index] as u64);
+        }
+        if let Some(annotations) = &self.annotations {
+            let (outdegree, reference_offset) = annotations[index];
+            values.extend([outdegree as u64, reference_offset]);
+        }
+        values
+    }
+}
+
+/// Extracts the subgraph selected by `args` from the graph at `src`, either a
+/// graph in the bin format or a BvGraph, whose random-access version is used
+/// (so the .ef file is required).
+pub fn extract(src: &Path, args: &SubgraphArgs) -> Result<Subgraph> {
+    if is_bin_graph(src) {
+        anyhow::ensure!(
+            !args.annotate,
+            "The nodes of a bin graph have no reference offset to annotate"
+        );
+        induced_subgraph(&BinGraphMmap::load(src)?, args)
+    } else {
+        dispatch_endianness!(src, extract_bvgraph(src, args))
+    }
+}
+
+fn extract_bvgraph<E: Endianness + 'static>(src: &Path, args: &SubgraphArgs) -> Result<Subgraph>
+where
+    MmapHelper<u32>: CodesReaderFactoryHelper<E>,
+    for<'a> LoadModeCodesReader<'a, E, Mmap>: BitSeek,
+{
+    let graph = BvGraph::with_basename(src)
+        .endianness::<E>()
+        .load()
+        .with_context(|| {
+            format!(
+                "Failed to load the random-access graph {:?}, the .ef file is required",
+                src.to_string_lossy()
+            )
+        })?;
+    let mut subgraph = induced_subgraph(&graph, args)?;
+
+    if args.annotate {
+        let index_of = subgraph.index_of();
+        let reference_offsets = reference_offsets::<E>(src, &index_of)?;
+        subgraph.annotations = Some(
+            subgraph
+                .nodes
+                .iter()
+                .zip(reference_offsets)
+                .map(|(&node, reference_offset)| (graph.outdegree(node), reference_offset))
+                .collect(),
+        );
+    }
+    Ok(subgraph)
+}
+
+/// Returns the subgraph of `graph` induced by the nodes selected by `args`,
+/// without annotations.
+fn induced_subgraph(graph: &impl RandomAccessGraph, args: &SubgraphArgs) -> Result<Subgraph> {
+    let num_nodes = graph.num_nodes();
+
+    let mut nodes = if let Some(range) = &args.range {
+        anyhow::ensure!(
+            range.end <= num_nodes,
+            "The range {:?} exceeds the number of nodes ({})",
+            range,
+            num_nodes
+        );
+        range.clone().collect::<Vec<_>>()
+    } else if let Some(seeds) = &args.seeds {
+        let mut visited = HashSet::new();
+        let mut frontier = Vec::new();
+        for &seed in seeds {
+            anyhow::ensure!(seed < num_nodes, "Invalid seed {}", seed);
+            if visited.insert(seed) {
+                frontier.push(seed);
+            }
+        }
+        for _ in 0..args.radius {
+            let mut next_frontier = Vec::new();
+            for node in frontier {
+                for successor in graph.successors(node) {
+                    if visited.insert(successor) {
+                        next_frontier.push(successor);
+                    }
+                }
+            }
+            anyhow::ensure!(
+                visited.len() <= args.max_nodes,
+                "The ball around the seeds has more than {} nodes, reduce the radius or increase --max-nodes",
+                args.max_nodes
+            );
+            frontier = next_frontier;
+        }
+        visited.into_iter().collect::<Vec<_>>()
+    } else if let Some(nodes_file) = &args.nodes_file {
+        read_nodes_file(nodes_file, num_nodes)?
+    } else {
+        anyhow::bail!("A selection of nodes is required: use --range, --seeds or --nodes-file");
+    };
+    nodes.sort_unstable();
+    nodes.dedup();
+    anyhow::ensure!(
+        nodes.len() <= args.max_nodes,
+        "{} nodes selected, but at most {} are allowed (see --max-nodes)",
+        nodes.len(),
+        args.max_nodes
+    );
+
+    let mut subgraph = Subgraph {
+        nodes,
+        arcs: Vec::new(),
+        annotations: None,
+        remap: args.remap,
+    };
+    let index_of = subgraph.index_of();
+    for (index, &node) in subgraph.nodes.iter().enumerate() {
+        for successor in graph.successors(node) {
+            if let Some(&successor_index) = index_of.get(&successor) {
+                subgraph.arcs.push((index, successor_index));
+            }
+        }
+    }
+    Ok(subgraph)
+}
+
+/// Scans the whole graph decoding the reference offsets of the nodes in `index_of`,
+/// returning them in the order of their indices (0 if the node has no reference).
+fn reference_offsets<E: Endianness + 'static>(
+    src: &Path,
+    index_of: &HashMap<usize, usize>,
+) -> Result<Vec<u64>>
+where
+    MmapHelper<u32>: CodesReaderFactoryHelper<E>,
+    for<'a> LoadModeCodesReader<'a, E, Mmap>: BitSeek,
+{
+    let reference_offsets = RefCell::new(vec![0; index_of.len()]);
+    let graph = BvGraphSeq::with_basename(src)
+        .endianness::<E>()
+        .load()?
+        .map_factory(|factory| {
+            ConsumerDecoderFactory::new(factory, |node, component, value| {
+                if component == BvGraphComponent::ReferenceOffset {
+                    if let Some(&index) = index_of.get(&node) {
+                        reference_offsets.borrow_mut()[index] = value;
+                    }
+                }
+            })
+        });
+
+    let mut pl = ProgressLogger::default();
+    pl.log_interval(Duration::from_secs(60));
+    pl.item_name("node")
+        .expected_updates(Some(graph.num_nodes()));
+    pl.start("Decoding the reference offsets...");
+
+    let mut iter = graph.iter();
+    while iter.next().is_some() {
+        pl.light_update();
+    }
+    pl.done();
+    drop(iter);
+    drop(graph);
+
+    Ok(reference_offsets.into_inner())
+}
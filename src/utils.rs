@@ -0,0 +1,107 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use webgraph::{prelude::*, transform::simplify, utils::MemoryUsage};
+
+// TODO: this functions are duplicated from webgraph but they are not exposed.
+//       duplicate them to keep a semantic as close as possible to webgraph graphs
+/// Creates all parent directories of the given file path.
+pub fn create_parent_dir(file_path: impl AsRef<Path>) -> Result<()> {
+    // ensure that the dst directory exists
+    if let Some(parent_dir) = file_path.as_ref().parent() {
+        std::fs::create_dir_all(parent_dir).with_context(|| {
+            format!(
+                "Failed to create the directory {:?}",
+                parent_dir.to_string_lossy()
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Appends `.extension` to `path`: unlike [`Path::with_extension`], an existing
+/// extension (e.g., the `.v2` of the basename `graph.v2`) is kept.
+pub fn append_extension(path: impl AsRef<Path>, extension: &str) -> PathBuf {
+    let mut path = path.as_ref().as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// Number of chunks assigned to each thread when a graph is scanned in parallel:
+/// having more chunks than threads balances the differences in decoding speed
+/// among chunks.
+pub const CHUNKS_PER_THREAD: usize = 4;
+
+/// Returns the symmetrized version of `graph`, without self-loops and duplicate
+/// arcs, computed with external-memory sorting (the batches are stored in TMPDIR).
+///
+/// The arcs of the graph are sorted together with those of its transpose, as the
+/// successors of a graph in the bin format need not be sorted.
+pub fn symmetrize(
+    graph: &impl SequentialGraph,
+    memory_usage: MemoryUsage,
+) -> Result<impl SequentialGraph> {
+    simplify(graph, memory_usage).context("Failed to symmetrize the graph")
+}
+
+/// Calls `$function::<E>($args...)`, where `E` is the [`Endianness`] of the BvGraph
+/// with basename `$basename`, returning an error if the endianness is unknown.
+///
+/// The function must be generic over the endianness, with the same bounds used
+/// by webgraph to load a graph with the given endianness (see, e.g.,
+/// [`dissect_graph`](crate::analyze::dissect::dissect_graph)).
+///
+/// [`Endianness`]: dsi_bitstream::prelude::Endianness
+#[macro_export]
+macro_rules! dispatch_endianness {
+    ($basename:expr, $function:ident ( $($arg:expr),* $(,)? )) => {
+        match ::webgraph::prelude::get_endianness($basename)?.as_str() {
+            <::dsi_bitstream::prelude::BE as ::dsi_bitstream::prelude::Endianness>::NAME => {
+                $function::<::dsi_bitstream::prelude::BE>($($arg),*)
+            }
+            <::dsi_bitstream::prelude::LE as ::dsi_bitstream::prelude::Endianness>::NAME => {
+                $function::<::dsi_bitstream::prelude::LE>($($arg),*)
+            }
+            e => ::anyhow::bail!("Unknown endianness: {}", e),
+        }
+    };
+}
+
+/// Calls `$function(&graph, $args...)`, where `graph` is the graph at `$path`:
+/// a [`BinGraphMmap`] if `$path` is a graph in the bin format, and otherwise the
+/// sequential BvGraph with basename `$path`, with the right endianness.
+///
+/// The function must be generic over the graph, which is a [`SequentialGraph`]
+/// (see, e.g., [`to_edges`](crate::to::edges::to_edges)).
+///
+/// [`BinGraphMmap`]: crate::bin_graph::BinGraphMmap
+/// [`SequentialGraph`]: webgraph::prelude::SequentialGraph
+#[macro_export]
+macro_rules! dispatch_graph {
+    ($path:expr, $function:ident ( $($arg:expr),* $(,)? )) => {{
+        let path = $path;
+        if $crate::bin_graph::is_bin_graph(path) {
+            $function(&$crate::bin_graph::BinGraphMmap::load(path)?, $($arg),*)
+        } else {
+            match ::webgraph::prelude::get_endianness(path)?.as_str() {
+                <::dsi_bitstream::prelude::BE as ::dsi_bitstream::prelude::Endianness>::NAME => {
+                    $function(
+                        &::webgraph::prelude::BvGraphSeq::with_basename(path)
+                            .endianness::<::dsi_bitstream::prelude::BE>()
+                            .load()?,
+                        $($arg),*
+                    )
+                }
+                <::dsi_bitstream::prelude::LE as ::dsi_bitstream::prelude::Endianness>::NAME => {
+                    $function(
+                        &::webgraph::prelude::BvGraphSeq::with_basename(path)
+                            .endianness::<::dsi_bitstream::prelude::LE>()
+                            .load()?,
+                        $($arg),*
+                    )
+                }
+                e => ::anyhow::bail!("Unknown endianness: {}", e),
+            }
+        }
+    }};
+}
@@ -0,0 +1,351 @@
+use anyhow::{Context, Result};
+
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use dsi_bitstream::{dispatch::factory::CodesReaderFactoryHelper, prelude::*};
+use dsi_progress_logger::prelude::*;
+use flate2::read::GzDecoder;
+use rayon::prelude::*;
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use webgraph::prelude::*;
+
+use crate::{
+    bin_graph::{is_bin_graph, BinGraphMmap},
+    dispatch_endianness,
+};
+
+pub const COMMAND_NAME: &str = "verify";
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Checks that two graph representations describe the same graph.",
+    long_about = "Checks that two graph representations describe the same graph, comparing the number of nodes, the number of arcs and the successor lists node by node, in parallel. Each input can be a BvGraph basename (the .ef file is required), a bin graph or an edge list (optionally compressed with gzip or zstd, detected by the .gz or .zst extension) with a pair of nodes per line. As edge lists can not represent isolated nodes with the largest identifiers, their number of nodes is not compared."
+)]
+pub struct CliArgs {
+    /// The first graph.
+    pub a: PathBuf,
+    /// The second graph.
+    pub b: PathBuf,
+
+    /// The maximum number of mismatching nodes to report.
+    #[arg(short, long, default_value_t = 10)]
+    pub max_mismatches: usize,
+
+    /// The number of threads (defaults to the number of available cores).
+    #[arg(short, long)]
+    pub threads: Option<usize>,
+}
+
+pub fn cli(command: Command) -> Command {
+    command.subcommand(CliArgs::augment_args(Command::new(COMMAND_NAME)).display_order(0))
+}
+
+/// The number of nodes compared in parallel before checking whether enough mismatches
+/// have been found: the mismatches of a chunk are sorted, so the first ones are reported.
+const CHUNK_SIZE: usize = 1 << 20;
+
+pub fn main(submatches: &ArgMatches) -> Result<()> {
+    let args = CliArgs::from_arg_matches(submatches)?;
+    let start = std::time::Instant::now();
+
+    let a = load(&args.a)?;
+    let b = load(&args.b)?;
+
+    if let (Some(a_nodes), Some(b_nodes)) = (a.num_nodes(), b.num_nodes()) {
+        anyhow::ensure!(
+            a_nodes == b_nodes,
+            "The graphs have a different number of nodes: {} and {}",
+            a_nodes,
+            b_nodes
+        );
+    }
+    let num_nodes = a.max_num_nodes().max(b.max_num_nodes());
+
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads.unwrap_or(0))
+        .build()?;
+
+    let mut pl = ProgressLogger::default();
+    pl.log_interval(Duration::from_secs(60));
+    pl.item_name("node").expected_updates(Some(num_nodes));
+    pl.start("Comparing the successor lists...");
+
+    let mut mismatches = Vec::new();
+    let (mut a_arcs, mut b_arcs) = (0_u64, 0_u64);
+    for chunk_start in (0..num_nodes).step_by(CHUNK_SIZE) {
+        let chunk = chunk_start..(chunk_start + CHUNK_SIZE).min(num_nodes);
+        let results = thread_pool.install(|| {
+            chunk
+                .clone()
+                .into_par_iter()
+                .map(|node| {
+                    let a_successors = a.successors(node);
+                    let b_successors = b.successors(node);
+                    let counts = (a_successors.len() as u64, b_successors.len() as u64);
+                    let mismatch = (a_successors != b_successors).then_some(Mismatch {
+                        node,
+                        a_successors,
+                        b_successors,
+                    });
+                    (counts, mismatch)
+                })
+                .collect::<Vec<_>>()
+        });
+        for ((a_count, b_count), mismatch) in results {
+            a_arcs += a_count;
+            b_arcs += b_count;
+            mismatches.extend(mismatch);
+        }
+        pl.update_with_count(chunk.len());
+        if !mismatches.is_empty() && mismatches.len() >= args.max_mismatches {
+            break;
+        }
+    }
+    pl.done();
+
+    for mismatch in mismatches.iter().take(args.max_mismatches) {
+        mismatch.report();
+    }
+    anyhow::ensure!(
+        mismatches.is_empty(),
+        "The successor lists of the graphs differ (reported the first {} mismatching nodes)",
+        mismatches.len().min(args.max_mismatches)
+    );
+    anyhow::ensure!(
+        a_arcs == b_arcs,
+        "The graphs have a different number of arcs: {} and {}",
+        a_arcs,
+        b_arcs
+    );
+    for (path, graph, num_arcs) in [(&args.a, &a, a_arcs), (&args.b, &b, b_arcs)] {
+        if let Some(declared) = graph.num_arcs() {
+            anyhow::ensure!(
+                declared == num_arcs,
+                "{:?} declares {} arcs, but its successor lists contain {}",
+                path.to_string_lossy(),
+                declared,
+                num_arcs
+            );
+        }
+    }
+
+    log::info!(
+        "The graphs are equal: {} nodes and {} arcs compared in {:.3} seconds",
+        num_nodes,
+        a_arcs,
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+/// A node whose successor lists differ in the two graphs.
+struct Mismatch {
+    node: usize,
+    a_successors: Vec<usize>,
+    b_successors: Vec<usize>,
+}
+
+impl Mismatch {
+    /// Logs the node with the successors that appear in only one of the two graphs.
+    fn report(&self) {
+        // the successor lists are sorted, so they are merged to find the differences
+        let (mut only_a, mut only_b) = (Vec::new(), Vec::new());
+        let (mut i, mut j) = (0, 0);
+        while i < self.a_successors.len() || j < self.b_successors.len() {
+            match (self.a_successors.get(i), self.b_successors.get(j)) {
+                (Some(x), Some(y)) if x == y => {
+                    i += 1;
+                    j += 1;
+                }
+                (Some(x), Some(y)) if x < y => {
+                    only_a.push(*x);
+                    i += 1;
+                }
+                (Some(x), None) => {
+                    only_a.push(*x);
+                    i += 1;
+                }
+                (_, Some(y)) => {
+                    only_b.push(*y);
+                    j += 1;
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+        log::error!(
+            "Node {}: outdegree {} and {}, successors only in the first graph: {:?}, only in the second graph: {:?}",
+            self.node,
+            self.a_successors.len(),
+            self.b_successors.len(),
+            only_a,
+            only_b
+        );
+    }
+}
+
+/// A graph loaded for the comparison, whatever its format.
+trait ComparableGraph: Sync {
+    /// The number of nodes, if the format stores it.
+    fn num_nodes(&self) -> Option<usize>;
+    /// An upper bound to the nodes with arcs.
+    fn max_num_nodes(&self) -> usize;
+    /// The number of arcs, if the format stores it.
+    fn num_arcs(&self) -> Option<u64>;
+    /// The sorted successors of `node`.
+    fn successors(&self, node: usize) -> Vec<usize>;
+}
+
+/// A random-access graph (a BvGraph or a bin graph).
+struct RandomAccess<G> {
+    graph: G,
+    /// Whether the successors are already sorted, as in a BvGraph: those of
+    /// a bin graph need not be, so they are sorted before the comparison.
+    sorted: bool,
+}
+
+impl<G: RandomAccessGraph + Sync> ComparableGraph for RandomAccess<G> {
+    fn num_nodes(&self) -> Option<usize> {
+        Some(self.graph.num_nodes())
+    }
+
+    fn max_num_nodes(&self) -> usize {
+        self.graph.num_nodes()
+    }
+
+    fn num_arcs(&self) -> Option<u64> {
+        self.graph.num_arcs_hint()
+    }
+
+    fn successors(&self, node: usize) -> Vec<usize> {
+        if node < self.graph.num_nodes() {
+            let mut successors = self.graph.successors(node).into_iter().collect::<Vec<_>>();
+            if !self.sorted {
+                successors.sort_unstable();
+            }
+            successors
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// An edge list, sorted and stored in memory as offsets and successors.
+struct EdgeList {
+    offsets: Vec<usize>,
+    successors: Vec<usize>,
+}
+
+impl ComparableGraph for EdgeList {
+    fn num_nodes(&self) -> Option<usize> {
+        None
+    }
+
+    fn max_num_nodes(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    fn num_arcs(&self) -> Option<u64> {
+        None
+    }
+
+    fn successors(&self, node: usize) -> Vec<usize> {
+        if node + 1 < self.offsets.len() {
+            self.successors[self.offsets[node]..self.offsets[node + 1]].to_vec()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Loads a graph detecting its format: a BvGraph if `path` is a basename with
+/// a .properties file, a bin graph if the file starts with its fingerprint,
+/// and an edge list otherwise.
+fn load(path: &Path) -> Result<Box<dyn ComparableGraph>> {
+    let mut properties = OsString::from(path.as_os_str());
+    properties.push(".properties");
+    if Path::new(&properties).exists() {
+        log::info!("Loading {:?} as a BvGraph", path.to_string_lossy());
+        dispatch_endianness!(path, load_bvgraph(path))
+    } else if is_bin_graph(path) {
+        log::info!("Loading {:?} as a bin graph", path.to_string_lossy());
+        Ok(Box::new(RandomAccess {
+            graph: BinGraphMmap::load(path)?,
+            sorted: false,
+        }))
+    } else {
+        log::info!("Loading {:?} as an edge list", path.to_string_lossy());
+        Ok(Box::new(load_edge_list(path)?))
+    }
+}
+
+fn load_bvgraph<E: Endianness + 'static>(basename: &Path) -> Result<Box<dyn ComparableGraph>>
+where
+    MmapHelper<u32>: CodesReaderFactoryHelper<E>,
+    for<'a> LoadModeCodesReader<'a, E, Mmap>: BitSeek,
+{
+    let graph = BvGraph::with_basename(basename)
+        .endianness::<E>()
+        .load()
+        .with_context(|| {
+            format!(
+                "Failed to load the random-access graph {:?}, the .ef file is required",
+                basename.to_string_lossy()
+            )
+        })?;
+    Ok(Box::new(RandomAccess {
+        graph,
+        sorted: true,
+    }))
+}
+
+/// Reads an edge list, skipping empty lines, comments (starting with # or %)
+/// and a header in the first line.
+fn load_edge_list(path: &Path) -> Result<EdgeList> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open the file {:?}", path.to_string_lossy()))?;
+    let reader: Box<dyn Read> = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("gz") => Box::new(GzDecoder::new(file)),
+        Some("zst") => {
+            Box::new(zstd::Decoder::new(file).context("Failed to create the zstd decoder")?)
+        }
+        _ => Box::new(file),
+    };
+
+    let mut arcs = Vec::new();
+    for (line_number, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line.context("Failed to read the edge list")?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('%') {
+            continue;
+        }
+        let mut nodes = line.split_whitespace().map(str::parse::<usize>);
+        match (nodes.next(), nodes.next()) {
+            (Some(Ok(src)), Some(Ok(dst))) => arcs.push((src, dst)),
+            _ if line_number == 0 => continue,
+            _ => anyhow::bail!("Invalid arc {:?} at line {}", line, line_number + 1),
+        }
+    }
+    arcs.par_sort_unstable();
+
+    let num_nodes = arcs
+        .iter()
+        .map(|&(src, dst)| src.max(dst) + 1)
+        .max()
+        .unwrap_or(0);
+    let mut offsets = vec![0; num_nodes + 1];
+    for &(src, _) in &arcs {
+        offsets[src + 1] += 1;
+    }
+    for node in 0..num_nodes {
+        offsets[node + 1] += offsets[node];
+    }
+    Ok(EdgeList {
+        offsets,
+        successors: arcs.into_iter().map(|(_, dst)| dst).collect(),
+    })
+}